@@ -0,0 +1,415 @@
+// #[derive(ToJson, FromJson)]，生成的代码使用 json_parser 的 Json、ToJson 和 FromJson
+// 不依赖 syn 和 quote：直接遍历 TokenStream 找出类型名、字段名和枚举成员，再把生成的代码拼成字符串解析回来
+// 字段的类型不需要解析，生成的代码里由编译器推断
+//
+// 生成的 JSON 结构：
+// - 有名字段的结构体写成对象，缺少的字段当作 null，所以 Option 类型的字段可以省略
+// - 只有一个字段的元组结构体写成这个字段本身，多个字段写成数组
+// - 单元结构体写成 null
+// - 枚举的单元成员写成成员名字符串，带数据的成员写成 {"成员名": 数据}，数据的写法和结构体相同
+//
+// 泛型参数和 where 子句原样抄到生成的 impl 上，每个类型参数再加上要实现的 trait 的约束，
+// 比如 Page<T> 生成 impl<T> ToJson for Page<T> where T: ToJson
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+#[derive(Default)]
+struct Generics {
+    // impl 后面尖括号里的参数（去掉默认值）、类型名后面尖括号里的参数，以及类型参数的名字
+    params: Vec<String>,
+    args: Vec<String>,
+    types: Vec<String>,
+    predicates: Vec<String>,
+}
+
+enum Fields {
+    Named(Vec<String>),
+    Tuple(usize),
+    Unit,
+}
+
+struct Variant {
+    name: String,
+    fields: Fields,
+}
+
+enum Item {
+    Struct(Fields),
+    Enum(Vec<Variant>),
+}
+
+#[proc_macro_derive(ToJson)]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    expand(input, to_json)
+}
+
+#[proc_macro_derive(FromJson)]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    expand(input, from_json)
+}
+
+fn expand(input: TokenStream, f: fn(&str, &Generics, &Item) -> String) -> TokenStream {
+    let code = match parse_item(input) {
+        Ok((name, generics, item)) => f(&name, &generics, &item),
+        Err(e) => format!("compile_error!({:?});", e),
+    };
+    code.parse().unwrap()
+}
+
+// 跳过开头的属性（包括文档注释）和可见性
+fn skip_attrs_and_vis(tokens: &[TokenTree]) -> &[TokenTree] {
+    let mut rest = tokens;
+    loop {
+        match rest {
+            [TokenTree::Punct(p), TokenTree::Group(_), tail @ ..] if p.as_char() == '#' => rest = tail,
+            [TokenTree::Ident(i), TokenTree::Group(g), tail @ ..]
+                if i.to_string() == "pub" && g.delimiter() == Delimiter::Parenthesis =>
+            {
+                rest = tail
+            }
+            [TokenTree::Ident(i), tail @ ..] if i.to_string() == "pub" => rest = tail,
+            _ => return rest,
+        }
+    }
+}
+
+// 按顶层的逗号切分，类型里尖括号中的逗号不算（-> 里的 > 也不算）
+fn split_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![];
+    let mut part = vec![];
+    let mut depth = 0;
+    let mut arrow = false;
+    for tt in stream {
+        if let TokenTree::Punct(p) = &tt {
+            match p.as_char() {
+                '<' => depth += 1,
+                '>' if !arrow => depth -= 1,
+                ',' if depth == 0 => {
+                    parts.push(std::mem::take(&mut part));
+                    continue;
+                }
+                _ => {}
+            }
+            arrow = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        part.push(tt);
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+fn to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+fn is_punct(tt: &TokenTree, c: char) -> bool {
+    matches!(tt, TokenTree::Punct(p) if p.as_char() == c)
+}
+
+// 尖括号外面的第一个 =，泛型参数的默认值从这里开始
+fn default_start(tokens: &[TokenTree]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, tt) in tokens.iter().enumerate() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' => depth -= 1,
+            TokenTree::Punct(p) if p.as_char() == '=' && depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+// 类型名后面的 <...>，返回泛型参数和剩下的 token
+fn parse_generics(tokens: &[TokenTree]) -> Result<(Generics, &[TokenTree]), String> {
+    let mut generics = Generics::default();
+    if !tokens.first().is_some_and(|tt| is_punct(tt, '<')) {
+        return Ok((generics, tokens));
+    }
+    let mut depth = 0;
+    let mut arrow = false;
+    let mut end = None;
+    for (i, tt) in tokens.iter().enumerate() {
+        if let TokenTree::Punct(p) = tt {
+            match p.as_char() {
+                '<' => depth += 1,
+                '>' if !arrow => depth -= 1,
+                _ => {}
+            }
+            arrow = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        if depth == 0 {
+            end = Some(i);
+            break;
+        }
+    }
+    let end = end.ok_or("expected `>`")?;
+    let inner: TokenStream = tokens[1..end].iter().cloned().collect();
+    for param in split_commas(inner) {
+        let param = &param[..default_start(&param).unwrap_or(param.len())];
+        let name = match param {
+            // 生命周期参数 'a
+            [TokenTree::Punct(p), TokenTree::Ident(name), ..] if p.as_char() == '\'' => format!("'{}", name),
+            [TokenTree::Ident(kw), TokenTree::Ident(name), ..] if kw.to_string() == "const" => name.to_string(),
+            [TokenTree::Ident(name), ..] => {
+                generics.types.push(name.to_string());
+                name.to_string()
+            }
+            _ => return Err("expected generic parameter".to_string()),
+        };
+        generics.params.push(to_string(param));
+        generics.args.push(name);
+    }
+    Ok((generics, &tokens[end + 1..]))
+}
+
+fn parse_predicates(tokens: &[TokenTree]) -> Vec<String> {
+    split_commas(tokens.iter().cloned().collect()).iter().map(|p| to_string(p)).collect()
+}
+
+impl Generics {
+    // impl<...> Trait for Name<...> where ...
+    fn impl_header(&self, name: &str, trait_path: &str) -> String {
+        let mut predicates = self.predicates.clone();
+        predicates.extend(self.types.iter().map(|t| format!("{}: {}", t, trait_path)));
+        let mut header = if self.params.is_empty() {
+            format!("impl {} for {}", trait_path, name)
+        } else {
+            format!("impl<{}> {} for {}<{}>", self.params.join(", "), trait_path, name, self.args.join(", "))
+        };
+        if !predicates.is_empty() {
+            header += &format!(" where {}", predicates.join(", "));
+        }
+        header
+    }
+}
+
+fn parse_fields(tt: Option<&TokenTree>) -> Result<Fields, String> {
+    match tt {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+            let mut names = vec![];
+            for field in split_commas(g.stream()) {
+                match skip_attrs_and_vis(&field) {
+                    [TokenTree::Ident(name), ..] => names.push(name.to_string()),
+                    _ => return Err("expected field name".to_string()),
+                }
+            }
+            Ok(Fields::Named(names))
+        }
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => Ok(Fields::Tuple(split_commas(g.stream()).len())),
+        _ => Ok(Fields::Unit),
+    }
+}
+
+fn parse_item(input: TokenStream) -> Result<(String, Generics, Item), String> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let (kind, name, rest) = match skip_attrs_and_vis(&tokens) {
+        [TokenTree::Ident(kind), TokenTree::Ident(name), rest @ ..] => (kind.to_string(), name.to_string(), rest),
+        _ => return Err("expected struct or enum".to_string()),
+    };
+    let (mut generics, rest) = parse_generics(rest)?;
+    // 元组结构体的 where 子句在字段后面，其余情况在大括号或者分号前面
+    let (body, clause) = match rest {
+        [body @ TokenTree::Group(g), clause @ ..] if g.delimiter() == Delimiter::Parenthesis => (Some(body), clause),
+        [clause @ .., body @ TokenTree::Group(g)] if g.delimiter() == Delimiter::Brace => (Some(body), clause),
+        _ => (None, rest),
+    };
+    if let [TokenTree::Ident(i), predicates @ ..] = clause {
+        if i.to_string() == "where" {
+            let end = predicates.iter().position(|tt| is_punct(tt, ';')).unwrap_or(predicates.len());
+            generics.predicates = parse_predicates(&predicates[..end]);
+        }
+    }
+    let item = match kind.as_str() {
+        "struct" => Item::Struct(parse_fields(body)?),
+        "enum" => {
+            let body = match body {
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => g.stream(),
+                _ => return Err("expected enum body".to_string()),
+            };
+            let mut variants = vec![];
+            for variant in split_commas(body) {
+                match skip_attrs_and_vis(&variant) {
+                    [TokenTree::Ident(name), tail @ ..] => variants.push(Variant {
+                        name: name.to_string(),
+                        fields: parse_fields(tail.first())?,
+                    }),
+                    _ => return Err("expected variant name".to_string()),
+                }
+            }
+            Item::Enum(variants)
+        }
+        _ => return Err("ToJson/FromJson can only be derived for structs and enums".to_string()),
+    };
+    Ok((name, generics, item))
+}
+
+// r#type 这样的原始标识符在 JSON 里写成 type
+fn key(field: &str) -> &str {
+    field.trim_start_matches("r#")
+}
+
+// 把 fields 转成 Json 的表达式，access(i, name) 返回字段的引用
+fn fields_to_json(fields: &Fields, access: impl Fn(usize, &str) -> String) -> String {
+    match fields {
+        Fields::Named(names) => {
+            let mut code = String::from("{ let mut object = ::std::collections::HashMap::new();");
+            for (i, name) in names.iter().enumerate() {
+                code += &format!(
+                    "object.insert(::std::string::String::from({:?}), ::std::boxed::Box::new(::json_parser::ToJson::to_json({})));",
+                    key(name),
+                    access(i, name)
+                );
+            }
+            code + "::json_parser::Json::OBJECT(object) }"
+        }
+        Fields::Tuple(1) => format!("::json_parser::ToJson::to_json({})", access(0, "")),
+        Fields::Tuple(n) => {
+            let elems: Vec<String> = (0..*n)
+                .map(|i| format!("::std::boxed::Box::new(::json_parser::ToJson::to_json({}))", access(i, "")))
+                .collect();
+            format!("::json_parser::Json::ARRAY(::std::vec![{}])", elems.join(", "))
+        }
+        Fields::Unit => "::json_parser::Json::NULL".to_string(),
+    }
+}
+
+// 从 json 这个变量读出 fields，构造 path（类型名或者枚举成员的路径）
+fn fields_from_json(path: &str, fields: &Fields) -> String {
+    match fields {
+        Fields::Named(names) => {
+            let inits: Vec<String> = names
+                .iter()
+                .map(|name| format!("{}: ::json_parser::serialize::from_field(object, {:?})?", name, key(name)))
+                .collect();
+            format!(
+                "let object = ::json_parser::serialize::expect_object(json)?; ::std::result::Result::Ok({} {{ {} }})",
+                path,
+                inits.join(", ")
+            )
+        }
+        Fields::Tuple(1) => format!("::std::result::Result::Ok({}(::json_parser::FromJson::from_json(json)?))", path),
+        Fields::Tuple(n) => {
+            let elems: Vec<String> = (0..*n).map(|i| format!("::json_parser::serialize::from_element(arr, {})?", i)).collect();
+            format!(
+                "let arr = ::json_parser::serialize::expect_array(json, {})?; ::std::result::Result::Ok({}({}))",
+                n,
+                path,
+                elems.join(", ")
+            )
+        }
+        Fields::Unit => format!(
+            "match json {{ ::json_parser::Json::NULL => ::std::result::Result::Ok({}), _ => ::std::result::Result::Err(::json_parser::FromJsonError::expected(\"null\", json)) }}",
+            path
+        ),
+    }
+}
+
+fn to_json(name: &str, generics: &Generics, item: &Item) -> String {
+    let body = match item {
+        Item::Struct(fields) => fields_to_json(fields, |i, field| match fields {
+            Fields::Named(_) => format!("&self.{}", field),
+            _ => format!("&self.{}", i),
+        }),
+        Item::Enum(variants) => {
+            let mut arms = String::new();
+            for v in variants.iter() {
+                let arm = match &v.fields {
+                    Fields::Unit => format!(
+                        "{}::{} => ::json_parser::Json::STRING(::std::string::String::from({:?})),",
+                        name, v.name, key(&v.name)
+                    ),
+                    // 字段绑定到生成的名字上，不会被 fields_to_json 里的 object 之类的局部变量遮住
+                    Fields::Named(names) => {
+                        let binds: Vec<String> = names.iter().enumerate().map(|(i, field)| format!("{}: __f{}", field, i)).collect();
+                        format!(
+                            "{}::{} {{ {} }} => ::json_parser::serialize::tagged({:?}, {}),",
+                            name,
+                            v.name,
+                            binds.join(", "),
+                            key(&v.name),
+                            fields_to_json(&v.fields, |i, _| format!("__f{}", i))
+                        )
+                    }
+                    Fields::Tuple(n) => {
+                        let binds: Vec<String> = (0..*n).map(|i| format!("__f{}", i)).collect();
+                        format!(
+                            "{}::{}({}) => ::json_parser::serialize::tagged({:?}, {}),",
+                            name,
+                            v.name,
+                            binds.join(", "),
+                            key(&v.name),
+                            fields_to_json(&v.fields, |i, _| format!("__f{}", i))
+                        )
+                    }
+                };
+                arms += &arm;
+            }
+            if variants.is_empty() {
+                // 没有成员的枚举不会有值，对引用 match 需要先解引用
+                "match *self {}".to_string()
+            } else {
+                format!("match self {{ {} }}", arms)
+            }
+        }
+    };
+    format!(
+        "{} {{ fn to_json(&self) -> ::json_parser::Json {{ {} }} }}",
+        generics.impl_header(name, "::json_parser::ToJson"),
+        body
+    )
+}
+
+fn from_json(name: &str, generics: &Generics, item: &Item) -> String {
+    let body = match item {
+        Item::Struct(fields) => fields_from_json(name, fields),
+        Item::Enum(variants) => {
+            let mut units = String::new();
+            let mut tagged = String::new();
+            for v in variants.iter() {
+                let path = format!("{}::{}", name, v.name);
+                match &v.fields {
+                    Fields::Unit => units += &format!("{:?} => ::std::result::Result::Ok({}),", key(&v.name), path),
+                    // 用闭包而不是嵌套的 fn，才能用到 impl 上的泛型参数
+                    fields => {
+                        tagged += &format!(
+                            "{:?} => {{
+                                let variant = |json: &::json_parser::Json| -> ::std::result::Result<Self, ::json_parser::FromJsonError> {{ {} }};
+                                variant(json).map_err(|e| e.with_key({:?}))
+                            }}",
+                            key(&v.name),
+                            fields_from_json(&path, fields),
+                            key(&v.name)
+                        )
+                    }
+                }
+            }
+            format!(
+                "let unknown = |tag: &str| -> ::std::result::Result<Self, ::json_parser::FromJsonError> {{ ::std::result::Result::Err(::json_parser::FromJsonError::new(::std::format!(\"unknown variant `{{}}`\", tag))) }};
+                match json {{
+                    ::json_parser::Json::STRING(tag) => match tag.as_str() {{ {} _ => unknown(tag) }},
+                    ::json_parser::Json::OBJECT(object) if object.len() == 1 => {{
+                        let (tag, json) = object.iter().next().unwrap();
+                        let json: &::json_parser::Json = json;
+                        match tag.as_str() {{ {} _ => unknown(tag) }}
+                    }}
+                    _ => ::std::result::Result::Err(::json_parser::FromJsonError::expected(\"string or single-key object\", json)),
+                }}",
+                units, tagged
+            )
+        }
+    };
+    format!(
+        "{} {{ fn from_json(json: &::json_parser::Json) -> ::std::result::Result<Self, ::json_parser::FromJsonError> {{ {} }} }}",
+        generics.impl_header(name, "::json_parser::FromJson"),
+        body
+    )
+}
@@ -0,0 +1,82 @@
+// 在使用者的 crate 里展开 derive，生成的代码只能通过 ::json_parser 的路径找到需要的东西
+
+use std::fmt::Debug;
+
+use json_parser::{json, FromJson, Json, ToJson};
+
+fn round_trip<T: ToJson + FromJson + PartialEq + Debug>(value: T, expect: Json) {
+    let json = value.to_json();
+    assert_eq!(json, expect);
+    assert_eq!(T::from_json(&json).unwrap(), value);
+}
+
+// 字段名和生成代码里的局部变量同名
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Line(u8, u8),
+    Rect { object: u8, json: bool, arr: Vec<u8> },
+}
+
+#[test]
+fn test_enum_variants() {
+    round_trip(Shape::Empty, json!("Empty"));
+    round_trip(Shape::Circle(1.5), json!({"Circle": 1.5}));
+    round_trip(Shape::Line(1, 2), json!({"Line": [1, 2]}));
+    round_trip(
+        Shape::Rect { object: 1, json: true, arr: vec![2] },
+        json!({"Rect": {"object": 1, "json": true, "arr": [2]}}),
+    );
+    let err = Shape::from_json(&json!({"Rect": {"object": 1, "json": 2}})).unwrap_err();
+    assert_eq!(err.to_string(), "$.Rect.json: expected boolean, found number");
+    assert_eq!(Shape::from_json(&json!("Square")).unwrap_err().to_string(), "$: unknown variant `Square`");
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<String>,
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Pair<A, B = u8>(A, B)
+where
+    A: Clone;
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+enum Tree<K: Clone, V>
+where
+    V: Debug,
+{
+    Leaf(V),
+    Node { key: K, children: Vec<Tree<K, V>> },
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Fixed<const N: usize>([u8; N]);
+
+#[derive(ToJson)]
+struct Borrowed<'a, T: ?Sized> {
+    name: &'a T,
+}
+
+#[test]
+fn test_generics() {
+    round_trip(Page { items: vec![1u8, 2], next: None }, json!({"items": [1, 2], "next": null}));
+    round_trip(
+        Page { items: vec![Shape::Empty], next: Some("b".to_string()) },
+        json!({"items": ["Empty"], "next": "b"}),
+    );
+    round_trip(Pair("a".to_string(), 1), json!(["a", 1]));
+
+    round_trip(
+        Tree::Node { key: 'k', children: vec![Tree::Leaf(true)] },
+        json!({"Node": {"key": "k", "children": [{"Leaf": true}]}}),
+    );
+    round_trip(Fixed([1, 2]), json!([1, 2]));
+    assert_eq!(Borrowed { name: "n" }.to_json(), json!({"name": "n"}));
+
+    let err = Page::<u8>::from_json(&json!({"items": [1, "2"]})).unwrap_err();
+    assert_eq!(err.to_string(), "$.items[1]: expected number, found string");
+}
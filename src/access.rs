@@ -0,0 +1,205 @@
+// 读取 Json 的便捷方法，类型不对或者 key、下标不存在时返回 None，不需要每次都写完整的 match
+//
+// 下标运算符 v["servers"][0]["port"] 在读的时候遇到不存在的路径返回共享的 NULL，不会 panic
+// 写的时候 NULL 会自动变成空对象，不存在的 key 自动插入 NULL，所以可以直接给深层的 key 赋值
+// 数组不会自动扩展，写入越界的下标和对非对象写入 key 一样会 panic
+
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+use crate::Json;
+
+impl Json {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::NULL)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::TRUE => Some(true),
+            Json::FALSE => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::NUMBER(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64_mut(&mut self) -> Option<&mut f64> {
+        match self {
+            Json::NUMBER(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// The number as an `i64`, if it is an integer within range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            // i64::MAX 转成 f64 会进位成 2^63，所以上界不能取等号
+            Json::NUMBER(n) if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::STRING(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_str_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Json::STRING(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::vec_box)]
+    pub fn as_array(&self) -> Option<&Vec<Box<Json>>> {
+        match self {
+            Json::ARRAY(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::vec_box)]
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Box<Json>>> {
+        match self {
+            Json::ARRAY(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&HashMap<String, Box<Json>>> {
+        match self {
+            Json::OBJECT(h) => Some(h),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut HashMap<String, Box<Json>>> {
+        match self {
+            Json::OBJECT(h) => Some(h),
+            _ => None,
+        }
+    }
+
+    /// The member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        self.as_object()?.get(key).map(|v| &**v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json> {
+        self.as_object_mut()?.get_mut(key).map(|v| &mut **v)
+    }
+
+    /// The element at index `i` of an array.
+    pub fn get_index(&self, i: usize) -> Option<&Json> {
+        self.as_array()?.get(i).map(|v| &**v)
+    }
+
+    pub fn get_index_mut(&mut self, i: usize) -> Option<&mut Json> {
+        self.as_array_mut()?.get_mut(i).map(|v| &mut **v)
+    }
+}
+
+static NULL: Json = Json::NULL;
+
+impl Index<&str> for Json {
+    type Output = Json;
+
+    fn index(&self, key: &str) -> &Json {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+impl Index<usize> for Json {
+    type Output = Json;
+
+    fn index(&self, i: usize) -> &Json {
+        self.get_index(i).unwrap_or(&NULL)
+    }
+}
+
+impl IndexMut<&str> for Json {
+    fn index_mut(&mut self, key: &str) -> &mut Json {
+        if self.is_null() {
+            *self = Json::OBJECT(HashMap::new());
+        }
+        match self {
+            Json::OBJECT(h) => h.entry(key.to_string()).or_insert_with(|| Box::new(Json::NULL)),
+            _ => panic!("cannot index into a non-object Json with key {:?}", key),
+        }
+    }
+}
+
+impl IndexMut<usize> for Json {
+    fn index_mut(&mut self, i: usize) -> &mut Json {
+        match self {
+            Json::ARRAY(arr) => {
+                let len = arr.len();
+                match arr.get_mut(i) {
+                    Some(v) => v,
+                    None => panic!("index {} out of bounds for Json array of length {}", i, len),
+                }
+            }
+            _ => panic!("cannot index into a non-array Json with {}", i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accessors() {
+        let mut json = crate::parse(r#"{"name": "x", "ok": true, "n": -3, "f": 1.5, "big": 1e19, "list": [null, 2]}"#).unwrap();
+        assert_eq!(json.get("name").and_then(Json::as_str), Some("x"));
+        assert_eq!(json.get("ok").and_then(Json::as_bool), Some(true));
+        assert_eq!(json.get("n").and_then(Json::as_i64), Some(-3));
+        assert_eq!(json.get("f").and_then(Json::as_i64), None);
+        assert_eq!(json.get("f").and_then(Json::as_f64), Some(1.5));
+        assert_eq!(json.get("big").and_then(Json::as_i64), None);
+        assert!(json.get("list").and_then(|l| l.get_index(0)).unwrap().is_null());
+        assert_eq!(json.get("list").and_then(|l| l.get_index(2)), None);
+        assert_eq!(json.get("missing"), None);
+        assert_eq!(json.get_index(0), None);
+        assert_eq!(json.get("name").and_then(Json::as_bool), None);
+
+        json.get_mut("name").and_then(Json::as_str_mut).unwrap().push('y');
+        *json.get_mut("f").and_then(Json::as_f64_mut).unwrap() += 1.0;
+        assert_eq!(json.get_mut("ok").and_then(Json::as_f64_mut), None);
+        *json.get_mut("list").and_then(|l| l.get_index_mut(1)).unwrap() = Json::TRUE;
+        json.get_mut("list").and_then(Json::as_array_mut).unwrap().push(Box::new(Json::FALSE));
+        json.as_object_mut().unwrap().remove("big");
+        assert_eq!(json, crate::parse(r#"{"name": "xy", "ok": true, "n": -3, "f": 2.5, "list": [null, true, false]}"#).unwrap());
+    }
+
+    #[test]
+    fn test_index() {
+        let mut json = crate::parse(r#"{"servers": [{"port": 80}], "name": "x"}"#).unwrap();
+        assert_eq!(json["servers"][0]["port"], Json::NUMBER(80.0));
+        assert!(json["servers"][1]["port"].is_null());
+        assert!(json["name"]["x"][3].is_null());
+
+        json["servers"][0]["port"] = Json::NUMBER(8080.0);
+        json["db"]["primary"]["host"] = Json::STRING("h".to_string());
+        assert_eq!(
+            json,
+            crate::parse(r#"{"servers": [{"port": 8080}], "name": "x", "db": {"primary": {"host": "h"}}}"#).unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_index_mut_out_of_bounds() {
+        let mut json = crate::parse("[1]").unwrap();
+        json[1] = Json::NULL;
+    }
+}
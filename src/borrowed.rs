@@ -0,0 +1,100 @@
+// 借用输入的 Json：没有转义的字符串和 key 直接指向输入，不做任何分配
+// 只有字符串里出现转义时才需要分配新的 String 保存反转义后的内容
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::bytes::{ByteParser, Value};
+
+#[allow(clippy::upper_case_acronyms, clippy::vec_box)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json<'a> {
+    NULL,
+    TRUE,
+    FALSE,
+    NUMBER(f64),
+    STRING(Cow<'a, str>),
+    ARRAY(Vec<Box<Json<'a>>>),
+    OBJECT(HashMap<Cow<'a, str>, Box<Json<'a>>>),
+}
+
+impl<'a> Json<'a> {
+    /// Converts into the owned `Json`, copying any strings still borrowed from the input.
+    pub fn into_owned(self) -> crate::Json {
+        match self {
+            Json::NULL => crate::Json::NULL,
+            Json::TRUE => crate::Json::TRUE,
+            Json::FALSE => crate::Json::FALSE,
+            Json::NUMBER(n) => crate::Json::NUMBER(n),
+            Json::STRING(s) => crate::Json::STRING(s.into_owned()),
+            Json::ARRAY(arr) => {
+                crate::Json::ARRAY(arr.into_iter().map(|v| Box::new(v.into_owned())).collect())
+            }
+            Json::OBJECT(h) => crate::Json::OBJECT(
+                h.into_iter()
+                    .map(|(k, v)| (k.into_owned(), Box::new(v.into_owned())))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> Value<'a> for Json<'a> {
+    type Object = HashMap<Cow<'a, str>, Box<Json<'a>>>;
+
+    fn literal(lit: crate::Json) -> Self {
+        match lit {
+            crate::Json::TRUE => Json::TRUE,
+            crate::Json::FALSE => Json::FALSE,
+            _ => Json::NULL,
+        }
+    }
+
+    fn number(n: f64) -> Self {
+        Json::NUMBER(n)
+    }
+
+    fn string(s: Cow<'a, str>) -> Self {
+        Json::STRING(s)
+    }
+
+    fn array(arr: Vec<Box<Self>>) -> Self {
+        Json::ARRAY(arr)
+    }
+
+    fn insert(obj: &mut Self::Object, k: Cow<'a, str>, v: Self) {
+        obj.insert(k, Box::new(v));
+    }
+
+    fn object(obj: Self::Object) -> Self {
+        Json::OBJECT(obj)
+    }
+}
+
+/// Parses `bytes` into a `Json` that borrows unescaped strings and keys from the input.
+pub fn parse(bytes: &[u8]) -> Result<Json<'_>, &'static str> {
+    ByteParser::new(bytes).parse_borrowed()
+}
+
+/// Same as `parse` for input that is already a `&str`.
+pub fn parse_str(s: &str) -> Result<Json<'_>, &'static str> {
+    parse(s.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_borrowed() {
+        let input = r#"{"plain": "abc", "esc\"aped": "a\nb"}"#;
+        let v = parse_str(input).unwrap();
+        let h = match &v {
+            Json::OBJECT(h) => h,
+            _ => panic!("expect object"),
+        };
+        assert!(matches!(h.get("plain").map(|v| &**v), Some(Json::STRING(Cow::Borrowed("abc")))));
+        assert!(matches!(h.keys().find(|k| k.starts_with("esc")), Some(Cow::Owned(_))));
+        assert_eq!(v.into_owned(), crate::parse_bytes(input.as_bytes()).unwrap());
+    }
+}
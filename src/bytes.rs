@@ -0,0 +1,537 @@
+// 直接在 &[u8] 上解析，思路来自 tutorial_01 的 ParseContext
+// 不需要先把整个输入解码成 char，UTF-8 只在字符串内部按需校验
+// 字符串之外出现的非 ASCII 字节本身就是语法错误，所以不用单独校验
+// 打开 jsonc 时空白的位置可以写注释；JSON5 的语法只由按字符解析的 Parser 实现
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str;
+
+use crate::lexer::comment_end;
+use crate::spans::{SpanMap, SpanRecorder};
+use crate::{borrowed, Json, ParseError, ParseOptions};
+
+// 解析器只负责语法，值由实现了 Value 的类型构造
+// 这样拥有所有权的 Json 和借用输入的 borrowed::Json 可以共用同一套语法代码
+pub(crate) trait Value<'a>: Sized {
+    type Object: Default;
+
+    fn literal(lit: Json) -> Self;
+    fn number(n: f64) -> Self;
+    fn string(s: Cow<'a, str>) -> Self;
+    fn array(arr: Vec<Box<Self>>) -> Self;
+    fn insert(obj: &mut Self::Object, k: Cow<'a, str>, v: Self);
+    fn object(obj: Self::Object) -> Self;
+}
+
+impl<'a> Value<'a> for Json {
+    type Object = HashMap<String, Box<Json>>;
+
+    fn literal(lit: Json) -> Self {
+        lit
+    }
+
+    fn number(n: f64) -> Self {
+        Json::NUMBER(n)
+    }
+
+    fn string(s: Cow<'a, str>) -> Self {
+        Json::STRING(s.into_owned())
+    }
+
+    fn array(arr: Vec<Box<Self>>) -> Self {
+        Json::ARRAY(arr)
+    }
+
+    fn insert(obj: &mut Self::Object, k: Cow<'a, str>, v: Self) {
+        obj.insert(k.into_owned(), Box::new(v));
+    }
+
+    fn object(obj: Self::Object) -> Self {
+        Json::OBJECT(obj)
+    }
+}
+
+pub struct ByteParser<'a> {
+    json_bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+    max_depth: usize,
+    comments: bool,
+    json5: bool,
+    spans: Option<SpanRecorder>,
+}
+
+impl<'a> ByteParser<'a> {
+    pub fn new(json_bytes: &'a [u8]) -> Self {
+        ByteParser::with_options(json_bytes, &ParseOptions::default())
+    }
+
+    pub fn with_options(json_bytes: &'a [u8], options: &ParseOptions) -> Self {
+        ByteParser {
+            json_bytes,
+            pos: 0,
+            depth: 0,
+            max_depth: options.max_depth,
+            comments: options.jsonc,
+            json5: options.json5,
+            spans: None,
+        }
+    }
+
+    /// Byte offset of the next unread byte.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Parses the whole input as a single JSON text.
+    pub fn parse(&mut self) -> Result<Json, &'static str> {
+        self.parse_document()
+    }
+
+    /// Like `parse`, but strings without escapes borrow from the input.
+    pub fn parse_borrowed(&mut self) -> Result<borrowed::Json<'a>, &'static str> {
+        self.parse_document()
+    }
+
+    /// Like `parse`, and also records where every value and key appears in the input.
+    pub fn parse_with_spans(&mut self) -> Result<(Json, SpanMap), &'static str> {
+        self.spans = Some(SpanRecorder::new());
+        let res = self.parse_document();
+        let spans = self.spans.take().unwrap();
+        Ok((res?, spans.finish(self.json_bytes)))
+    }
+
+    // 词法分析器和 CST 用来切分字符串、数字和字面量，保证它们和解析器的切分完全一致
+    // 返回 bytes 开头的这个 token 的值和长度，后面的内容不检查
+    pub(crate) fn scalar(bytes: &'a [u8]) -> Result<(Json, usize), &'static str> {
+        let mut parser = ByteParser::new(bytes);
+        let v = match parser.peek() {
+            Some(b'[') | Some(b'{') => return Err("parse value error"),
+            _ => parser.parse_token()?,
+        };
+        Ok((v, parser.pos))
+    }
+
+    /// Turns the parser into an iterator over consecutive top-level values.
+    pub fn into_stream(self) -> ValueStream<'a> {
+        ValueStream {
+            parser: self,
+            done: false,
+        }
+    }
+
+    fn parse_document<V: Value<'a>>(&mut self) -> Result<V, &'static str> {
+        self.check_options()?;
+        self.parse_whitespace()?;
+        let v = self.parse_value()?;
+        self.parse_whitespace()?;
+        if self.pos != self.json_bytes.len() {
+            return Err("parse root not singular");
+        }
+        Ok(v)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json_bytes.get(self.pos).copied()
+    }
+
+    // JSON5 的语法由按字符解析的 Parser 实现，这里只支持标准 JSON 和 JSONC 的注释
+    fn check_options(&self) -> Result<(), &'static str> {
+        if self.json5 {
+            return Err("parse json5 not supported");
+        }
+        Ok(())
+    }
+
+    fn parse_whitespace(&mut self) -> Result<(), &'static str> {
+        loop {
+            match self.peek() {
+                Some(b' ') | Some(b'\n') | Some(b'\r') | Some(b'\t') => self.pos += 1,
+                Some(b'/') if self.comments => self.pos = comment_end(self.json_bytes, self.pos)?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn parse_value<V: Value<'a>>(&mut self) -> Result<V, &'static str> {
+        let start = self.pos;
+        let v = self.parse_token()?;
+        if let Some(spans) = &mut self.spans {
+            spans.value(start, self.pos);
+        }
+        Ok(v)
+    }
+
+    fn parse_token<V: Value<'a>>(&mut self) -> Result<V, &'static str> {
+        match self.peek() {
+            Some(b'n') => self.parse_literals("null", Json::NULL),
+            Some(b't') => self.parse_literals("true", Json::TRUE),
+            Some(b'f') => self.parse_literals("false", Json::FALSE),
+            Some(b'"') => Ok(V::string(self.parse_string()?)),
+            Some(b'[') => self.parse_nested(Self::parse_array),
+            Some(b'{') => self.parse_nested(Self::parse_object),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err("parse value error"),
+            None => Err("expect value error"),
+        }
+    }
+
+    fn parse_nested<V, F>(&mut self, f: F) -> Result<V, &'static str>
+    where
+        F: FnOnce(&mut Self) -> Result<V, &'static str>,
+    {
+        if self.depth >= self.max_depth {
+            return Err("parse nesting too deep");
+        }
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        res
+    }
+
+    fn parse_literals<V: Value<'a>>(&mut self, s: &str, v: Json) -> Result<V, &'static str> {
+        if !self.json_bytes[self.pos..].starts_with(s.as_bytes()) {
+            return Err("parse invalid literal");
+        }
+        self.pos += s.len();
+        Ok(V::literal(v))
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    // number = [ minus ] int [ frac ] [ exp ]
+    fn parse_number<V: Value<'a>>(&mut self) -> Result<V, &'static str> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.skip_digits();
+            }
+            _ => return Err("parse invalid number"),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.skip_digits() == 0 {
+                return Err("parse invalid number");
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                return Err("parse invalid number");
+            }
+        }
+        // 数字部分只包含 ASCII，一定是合法的 UTF-8
+        let text = str::from_utf8(&self.json_bytes[start..self.pos]).unwrap();
+        let n: f64 = text.parse().map_err(|_| "parse invalid number")?;
+        if n.is_infinite() {
+            return Err("parse number too big");
+        }
+        Ok(V::number(n))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, &'static str> {
+        let hex = self.json_bytes.get(self.pos..self.pos + 4).ok_or("parse invalid unicode hex")?;
+        let mut u = 0;
+        for &b in hex {
+            let d = (b as char).to_digit(16).ok_or("parse invalid unicode hex")?;
+            u = u << 4 | d;
+        }
+        self.pos += 4;
+        Ok(u)
+    }
+
+    fn parse_escape(&mut self, s: &mut String) -> Result<(), &'static str> {
+        let ch = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                let mut u = self.parse_hex4()?;
+                if (0xDC00..=0xDFFF).contains(&u) {
+                    return Err("parse invalid unicode surrogate");
+                }
+                if (0xD800..=0xDBFF).contains(&u) {
+                    if !self.json_bytes[self.pos..].starts_with(b"\\u") {
+                        return Err("parse invalid unicode surrogate");
+                    }
+                    self.pos += 2;
+                    let low = self.parse_hex4()?;
+                    if !(0xDC00..=0xDFFF).contains(&low) {
+                        return Err("parse invalid unicode surrogate");
+                    }
+                    u = 0x10000 + ((u - 0xD800) << 10) + (low - 0xDC00);
+                }
+                s.push(char::from_u32(u).ok_or("parse invalid unicode surrogate")?);
+                return Ok(());
+            }
+            Some(_) => return Err("parse invalid string escape"),
+            None => return Err("parse miss quotation mark"),
+        };
+        self.pos += 1;
+        s.push(ch);
+        Ok(())
+    }
+
+    // 没有转义的字符串直接借用输入，遇到第一个转义才开始分配
+    fn parse_string(&mut self) -> Result<Cow<'a, str>, &'static str> {
+        let json_bytes = self.json_bytes;
+        let mut s = String::new();
+        let mut escaped = false;
+        self.pos += 1;
+        loop {
+            // 先找出一段不需要特殊处理的字节，整段校验 UTF-8 后一次性拷贝
+            // 这一段只会在 ASCII 字节处结束，不会截断多字节序列
+            let start = self.pos;
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            let run = str::from_utf8(&json_bytes[start..self.pos]).map_err(|_| "parse invalid utf-8")?;
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    if !escaped {
+                        return Ok(Cow::Borrowed(run));
+                    }
+                    s.push_str(run);
+                    return Ok(Cow::Owned(s));
+                }
+                Some(b'\\') => {
+                    escaped = true;
+                    s.push_str(run);
+                    self.pos += 1;
+                    self.parse_escape(&mut s)?;
+                }
+                Some(_) => return Err("parse invalid string char"),
+                None => return Err("parse miss quotation mark"),
+            }
+        }
+    }
+
+    fn parse_array<V: Value<'a>>(&mut self) -> Result<V, &'static str> {
+        let mut arr: Vec<Box<V>> = vec![];
+        self.pos += 1;
+        self.parse_whitespace()?;
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(V::array(arr));
+        }
+        loop {
+            self.parse_whitespace()?;
+            let saved = self.spans.as_mut().map(|spans| spans.enter_index(arr.len()));
+            arr.push(Box::new(self.parse_value()?));
+            if let (Some(spans), Some(len)) = (&mut self.spans, saved) {
+                spans.leave(len);
+            }
+            self.parse_whitespace()?;
+            match self.peek() {
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(V::array(arr));
+                }
+                Some(b',') => self.pos += 1,
+                _ => return Err("parse invalid array"),
+            }
+        }
+    }
+
+    fn parse_object<V: Value<'a>>(&mut self) -> Result<V, &'static str> {
+        let mut h = V::Object::default();
+        self.pos += 1;
+        self.parse_whitespace()?;
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(V::object(h));
+        }
+        loop {
+            self.parse_whitespace()?;
+            if self.peek() != Some(b'"') {
+                return Err("parse miss key");
+            }
+            let start = self.pos;
+            let k = self.parse_string()?;
+            let end = self.pos;
+            let saved = self.spans.as_mut().map(|spans| spans.enter_key(&k, start, end));
+            self.parse_whitespace()?;
+            if self.peek() != Some(b':') {
+                return Err("parse miss colon");
+            }
+            self.pos += 1;
+            self.parse_whitespace()?;
+            let v = self.parse_value()?;
+            if let (Some(spans), Some(len)) = (&mut self.spans, saved) {
+                spans.leave(len);
+            }
+            V::insert(&mut h, k, v);
+            self.parse_whitespace()?;
+            match self.peek() {
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(V::object(h));
+                }
+                Some(b',') => self.pos += 1,
+                _ => return Err("parse invalid object"),
+            }
+        }
+    }
+}
+
+/// Parses a complete JSON text from raw bytes, validating UTF-8 along the way.
+pub fn parse_bytes(bytes: &[u8]) -> Result<Json, &'static str> {
+    ByteParser::new(bytes).parse()
+}
+
+// 连续的顶层值，比如 docker 和 jq 的输出：{"a":1}{"b":2}[3] 或者用空白分隔的多个值
+// 和 parse 不同，第一个值后面剩下的输入不算错误，而是继续解析成下一个值
+// 出错以后无法确定下一个值从哪里开始，所以迭代直接结束
+pub struct ValueStream<'a> {
+    parser: ByteParser<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for ValueStream<'a> {
+    /// Each value together with the byte offset where it starts.
+    type Item = Result<(usize, Json), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Err(e) = self.parser.check_options().and_then(|_| self.parser.parse_whitespace()) {
+            self.done = true;
+            return Some(Err(ParseError::Syntax(e)));
+        }
+        if self.parser.peek().is_none() {
+            self.done = true;
+            return None;
+        }
+        let offset = self.parser.pos;
+        match self.parser.parse_value() {
+            Ok(v) => Some(Ok((offset, v))),
+            Err(e) => {
+                self.done = true;
+                Some(Err(ParseError::Syntax(e)))
+            }
+        }
+    }
+}
+
+/// Parses concatenated JSON values, with or without whitespace between them.
+pub fn parse_stream(bytes: &[u8]) -> ValueStream<'_> {
+    ByteParser::new(bytes).into_stream()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_parse_error(s: &[u8], e: &str) {
+        assert_eq!(parse_bytes(s), Err(e));
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_bytes(b" null "), Ok(Json::NULL));
+        assert_eq!(parse_bytes(b"-1.5e2"), Ok(Json::NUMBER(-150.0)));
+        assert_eq!(parse_bytes(b"\"a\\u00e9\\uD834\\uDD1E\""), Ok(Json::STRING("a\u{e9}\u{1D11E}".to_string())));
+        assert_eq!(parse_bytes("\"\u{4e2d}\"".as_bytes()), Ok(Json::STRING("\u{4e2d}".to_string())));
+        assert_eq!(parse_bytes(b"[ ]"), Ok(Json::ARRAY(vec![])));
+        assert_eq!(parse_bytes(b"{ }"), Ok(Json::OBJECT(HashMap::new())));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        test_parse_error(b"", "expect value error");
+        test_parse_error(b"nul", "parse invalid literal");
+        test_parse_error(b"null x", "parse root not singular");
+        test_parse_error(b"01", "parse root not singular");
+        test_parse_error(b"1.", "parse invalid number");
+        test_parse_error(b"1e309", "parse number too big");
+        test_parse_error(b"\"abc", "parse miss quotation mark");
+        test_parse_error(b"\"\\x\"", "parse invalid string escape");
+        test_parse_error(b"\"\\uD800\"", "parse invalid unicode surrogate");
+        test_parse_error(b"\"\xff\"", "parse invalid utf-8");
+        test_parse_error(b"\"\xe4\xb8\"", "parse invalid utf-8");
+        test_parse_error(b"[1 2]", "parse invalid array");
+        test_parse_error(b"{1:1}", "parse miss key");
+        test_parse_error(b"{\"a\" 1}", "parse miss colon");
+        test_parse_error(&[b'['; 129], "parse nesting too deep");
+    }
+
+    #[test]
+    fn test_parse_with_spans() {
+        let input = "{\n  \"name\": \"\u{4e2d}\u{6587}\",\n  \"servers\": [\n    {\"port\": 80},\n    {\"port\": 8080}\n  ]\n}";
+        let (json, spans) = ByteParser::new(input.as_bytes()).parse_with_spans().unwrap();
+        assert_eq!(json, crate::parse(input).unwrap());
+        let port = spans.value("$.servers[1].port").unwrap();
+        assert_eq!(&input[port.start..port.end], "8080");
+        assert_eq!((port.line, port.column), (5, 14));
+        let key = spans.key("$.servers[1].port").unwrap();
+        assert_eq!(&input[key.start..key.end], "\"port\"");
+        assert_eq!((key.line, key.column), (5, 6));
+        let root = spans.value("$").unwrap();
+        assert_eq!((root.start, root.end, root.line, root.column), (0, input.len(), 1, 1));
+        assert_eq!(spans.value("$.name").unwrap().end, input.find(',').unwrap());
+        assert_eq!(spans.value("$.servers[2]"), None);
+        assert_eq!(spans.key("$"), None);
+    }
+
+    #[test]
+    fn test_options() {
+        let jsonc = ParseOptions {
+            jsonc: true,
+            ..ParseOptions::default()
+        };
+        let input = b"/* a */ {\"a\": [1, // one\n 2]} // end";
+        let (json, spans) = ByteParser::with_options(input, &jsonc).parse_with_spans().unwrap();
+        assert_eq!(json, crate::parse("{\"a\": [1, 2]}").unwrap());
+        assert_eq!(spans.value("$.a[1]").unwrap().line, 2);
+        assert_eq!(ByteParser::with_options(b"[1 /* x", &jsonc).parse(), Err("parse unterminated comment"));
+        assert_eq!(ByteParser::new(b"[1 /* x */]").parse(), Err("parse invalid array"));
+
+        let json5 = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(ByteParser::with_options(b"1", &json5).parse(), Err("parse json5 not supported"));
+        let mut stream = ByteParser::with_options(b"1", &json5).into_stream();
+        assert_eq!(stream.next().unwrap().unwrap_err().to_string(), "parse json5 not supported");
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_stream() {
+        let got: Vec<(usize, Json)> = parse_stream(b"{\"a\":1}{\"b\":2}[3] 4\n\"x\"")
+            .map(Result::unwrap)
+            .collect();
+        let offsets: Vec<usize> = got.iter().map(|(i, _)| *i).collect();
+        assert_eq!(offsets, vec![0, 7, 14, 18, 20]);
+        assert_eq!(got[2].1, Json::ARRAY(vec![Box::new(Json::NUMBER(3.0))]));
+
+        let got: Vec<_> = parse_stream(b"[1] [2,] [3]").collect();
+        assert_eq!(got.len(), 2);
+        assert!(matches!(got[1], Err(ParseError::Syntax("parse value error"))));
+    }
+}
@@ -0,0 +1,182 @@
+// 终端彩色输出：按 token 类型给 key、字符串、数字、布尔值和 null 上色
+// 输出不是终端或者设置了 NO_COLOR 环境变量时自动退化为纯文本
+
+use std::env;
+use std::fmt::{self, Write};
+use std::io::{self, IsTerminal};
+
+use crate::{write_escaped_str, write_number, Json};
+
+const RESET: &str = "\x1b[0m";
+
+/// ANSI escape sequences used for each kind of token.
+///
+/// An empty string leaves that kind of token uncolored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub key: &'static str,
+    pub string: &'static str,
+    pub number: &'static str,
+    pub boolean: &'static str,
+    pub null: &'static str,
+    pub punctuation: &'static str,
+}
+
+impl Theme {
+    /// Basic 16-color palette, readable on both dark and light backgrounds.
+    pub const DEFAULT: Theme = Theme {
+        key: "\x1b[1;34m",
+        string: "\x1b[32m",
+        number: "\x1b[36m",
+        boolean: "\x1b[33m",
+        null: "\x1b[90m",
+        punctuation: "",
+    };
+
+    /// 256-color palette in the style of Monokai.
+    pub const MONOKAI: Theme = Theme {
+        key: "\x1b[38;5;81m",
+        string: "\x1b[38;5;186m",
+        number: "\x1b[38;5;141m",
+        boolean: "\x1b[38;5;197m",
+        null: "\x1b[38;5;197m",
+        punctuation: "\x1b[38;5;231m",
+    };
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DEFAULT
+    }
+}
+
+/// Whether colored output should be written to stdout.
+///
+/// Follows <https://no-color.org>: a non-empty `NO_COLOR` disables color.
+pub fn color_enabled() -> bool {
+    match env::var_os("NO_COLOR") {
+        Some(v) if !v.is_empty() => false,
+        _ => io::stdout().is_terminal(),
+    }
+}
+
+/// Writes `json` pretty-printed, coloring tokens with `theme` when one is given.
+pub fn write_json<W: Write>(out: &mut W, json: &Json, theme: Option<&Theme>) -> fmt::Result {
+    write_value(out, json, theme, 0)
+}
+
+/// Pretty-prints `json` into a new string, colored with `theme`.
+pub fn to_colored_string(json: &Json, theme: &Theme) -> String {
+    let mut s = String::new();
+    // 写入 String 不会失败
+    write_json(&mut s, json, Some(theme)).unwrap();
+    s
+}
+
+/// Prints `json` to stdout, colored with the default theme if the output is a terminal.
+pub fn print(json: &Json) {
+    let theme = Theme::default();
+    let mut s = String::new();
+    let theme = if color_enabled() { Some(&theme) } else { None };
+    write_json(&mut s, json, theme).unwrap();
+    println!("{}", s);
+}
+
+fn paint<W, F>(out: &mut W, color: Option<&'static str>, f: F) -> fmt::Result
+    where W: Write,
+          F: FnOnce(&mut W) -> fmt::Result
+{
+    match color {
+        Some(c) if !c.is_empty() => {
+            out.write_str(c)?;
+            f(out)?;
+            out.write_str(RESET)
+        }
+        _ => f(out),
+    }
+}
+
+fn indent<W: Write>(out: &mut W, depth: usize) -> fmt::Result {
+    for _ in 0..depth {
+        out.write_str("  ")?;
+    }
+    Ok(())
+}
+
+fn write_value<W: Write>(out: &mut W, json: &Json, theme: Option<&Theme>, depth: usize) -> fmt::Result {
+    let punct = theme.map(|t| t.punctuation);
+    match json {
+        Json::NULL => paint(out, theme.map(|t| t.null), |o| o.write_str("null")),
+        Json::TRUE => paint(out, theme.map(|t| t.boolean), |o| o.write_str("true")),
+        Json::FALSE => paint(out, theme.map(|t| t.boolean), |o| o.write_str("false")),
+        Json::NUMBER(n) if !n.is_finite() => paint(out, theme.map(|t| t.null), |o| write_number(o, *n)),
+        Json::NUMBER(n) => paint(out, theme.map(|t| t.number), |o| write_number(o, *n)),
+        Json::STRING(s) => paint(out, theme.map(|t| t.string), |o| write_escaped_str(o, s)),
+        Json::ARRAY(arr) => {
+            if arr.is_empty() {
+                return paint(out, punct, |o| o.write_str("[]"));
+            }
+            paint(out, punct, |o| o.write_char('['))?;
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    paint(out, punct, |o| o.write_char(','))?;
+                }
+                out.write_char('\n')?;
+                indent(out, depth + 1)?;
+                write_value(out, v, theme, depth + 1)?;
+            }
+            out.write_char('\n')?;
+            indent(out, depth)?;
+            paint(out, punct, |o| o.write_char(']'))
+        }
+        Json::OBJECT(h) => {
+            if h.is_empty() {
+                return paint(out, punct, |o| o.write_str("{}"));
+            }
+            paint(out, punct, |o| o.write_char('{'))?;
+            for (i, (k, v)) in h.iter().enumerate() {
+                if i > 0 {
+                    paint(out, punct, |o| o.write_char(','))?;
+                }
+                out.write_char('\n')?;
+                indent(out, depth + 1)?;
+                paint(out, theme.map(|t| t.key), |o| write_escaped_str(o, k))?;
+                paint(out, punct, |o| o.write_str(": "))?;
+                write_value(out, v, theme, depth + 1)?;
+            }
+            out.write_char('\n')?;
+            indent(out, depth)?;
+            paint(out, punct, |o| o.write_char('}'))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_colored_string() {
+        let json = crate::parse(r#"{"k": [1, true, null, "s"]}"#).unwrap();
+        // DEFAULT 的 punctuation 是空字符串，括号和逗号不上色
+        let expect = "{\n  \x1b[1;34m\"k\"\x1b[0m: [\n    \x1b[36m1\x1b[0m,\n    \x1b[33mtrue\x1b[0m,\n    \x1b[90mnull\x1b[0m,\n    \x1b[32m\"s\"\x1b[0m\n  ]\n}";
+        assert_eq!(to_colored_string(&json, &Theme::DEFAULT), expect);
+
+        let json = crate::parse("[false, {}]").unwrap();
+        let p = |s: &str| format!("\x1b[38;5;231m{}\x1b[0m", s);
+        let expect = format!("{}\n  \x1b[38;5;197mfalse\x1b[0m{}\n  {}\n{}", p("["), p(","), p("{}"), p("]"));
+        assert_eq!(to_colored_string(&json, &Theme::MONOKAI), expect);
+    }
+
+    #[test]
+    fn test_write_json_plain() {
+        let json = crate::parse(r#"{"a": [1.5, "x\n"], "b": {}}"#).unwrap();
+        let mut s = String::new();
+        write_json(&mut s, &json, None).unwrap();
+        assert!(!s.contains('\x1b'));
+        assert_eq!(crate::parse(&s).unwrap(), json);
+        let mut s = String::new();
+        write_json(&mut s, &crate::parse(r#"{"a": []}"#).unwrap(), None).unwrap();
+        assert_eq!(s, "{\n  \"a\": []\n}");
+    }
+}
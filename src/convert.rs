@@ -0,0 +1,290 @@
+// 常用的 Rust 类型和 Json 之间的转换，json! 宏里的表达式都通过 From 转换
+//
+// 所有数字都存成 f64，超过 2^53 的整数会丢失精度
+// 反方向用 TryFrom，类型不对、整数带小数或者超出范围时返回 TypeError
+// Option<T> 的 TryFrom 只能给具体的类型实现，因为标准库已经有 From<T> for Option<T>，
+// 泛型实现会和 TryFrom 的默认实现冲突
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use crate::Json;
+
+/// A `Json` value that could not be converted to the requested type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub expected: &'static str,
+    pub found: String,
+}
+
+impl TypeError {
+    pub(crate) fn new(expected: &'static str, found: &Json) -> Self {
+        TypeError {
+            expected,
+            found: found.type_name().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl error::Error for TypeError {}
+
+impl Json {
+    /// The name of the value's JSON type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Json::NULL => "null",
+            Json::TRUE | Json::FALSE => "boolean",
+            Json::NUMBER(_) => "number",
+            Json::STRING(_) => "string",
+            Json::ARRAY(_) => "array",
+            Json::OBJECT(_) => "object",
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        if b {
+            Json::TRUE
+        } else {
+            Json::FALSE
+        }
+    }
+}
+
+impl TryFrom<Json> for bool {
+    type Error = TypeError;
+
+    fn try_from(json: Json) -> Result<Self, TypeError> {
+        json.as_bool().ok_or_else(|| TypeError::new("boolean", &json))
+    }
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Json {
+                fn from(n: $t) -> Self {
+                    Json::NUMBER(n as f64)
+                }
+            }
+
+            impl TryFrom<Json> for $t {
+                type Error = TypeError;
+
+                fn try_from(json: Json) -> Result<Self, TypeError> {
+                    let n = json.as_f64().ok_or_else(|| TypeError::new(stringify!($t), &json))?;
+                    // MIN 和 MAX + 1 都是 2 的幂，转成 f64 是精确的，范围内的整数再转回 $t 也是精确的
+                    // 不经过 i128 转换，所以 u128 的上半部分也能取到
+                    let (min, end) = (<$t>::MIN as f64, <$t>::MAX as f64 + 1.0);
+                    if n.fract() == 0.0 && n >= min && n < end {
+                        return Ok(n as $t);
+                    }
+                    Err(TypeError {
+                        expected: stringify!($t),
+                        found: format!("number {}", n),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl From<f32> for Json {
+    fn from(n: f32) -> Self {
+        Json::NUMBER(f64::from(n))
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Self {
+        Json::NUMBER(n)
+    }
+}
+
+impl TryFrom<Json> for f32 {
+    type Error = TypeError;
+
+    fn try_from(json: Json) -> Result<Self, TypeError> {
+        json.as_f64().map(|n| n as f32).ok_or_else(|| TypeError::new("f32", &json))
+    }
+}
+
+impl TryFrom<Json> for f64 {
+    type Error = TypeError;
+
+    fn try_from(json: Json) -> Result<Self, TypeError> {
+        json.as_f64().ok_or_else(|| TypeError::new("f64", &json))
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::STRING(s.to_string())
+    }
+}
+
+impl From<&String> for Json {
+    fn from(s: &String) -> Self {
+        Json::STRING(s.clone())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::STRING(s)
+    }
+}
+
+impl TryFrom<Json> for String {
+    type Error = TypeError;
+
+    fn try_from(json: Json) -> Result<Self, TypeError> {
+        match json {
+            Json::STRING(s) => Ok(s),
+            _ => Err(TypeError::new("string", &json)),
+        }
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Json::NULL, Into::into)
+    }
+}
+
+macro_rules! impl_try_from_option {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<Json> for Option<$t> {
+                type Error = TypeError;
+
+                fn try_from(json: Json) -> Result<Self, TypeError> {
+                    match json {
+                        Json::NULL => Ok(None),
+                        _ => <$t>::try_from(json).map(Some),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_option!(bool, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, String);
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(v: Vec<T>) -> Self {
+        Json::ARRAY(v.into_iter().map(|x| Box::new(x.into())).collect())
+    }
+}
+
+impl<T: Clone + Into<Json>> From<&[T]> for Json {
+    fn from(v: &[T]) -> Self {
+        Json::ARRAY(v.iter().map(|x| Box::new(x.clone().into())).collect())
+    }
+}
+
+impl<T> TryFrom<Json> for Vec<T>
+    where T: TryFrom<Json, Error = TypeError>
+{
+    type Error = TypeError;
+
+    fn try_from(json: Json) -> Result<Self, TypeError> {
+        match json {
+            Json::ARRAY(arr) => arr.into_iter().map(|x| T::try_from(*x)).collect(),
+            _ => Err(TypeError::new("array", &json)),
+        }
+    }
+}
+
+impl<T: Into<Json>> From<HashMap<String, T>> for Json {
+    fn from(h: HashMap<String, T>) -> Self {
+        Json::OBJECT(h.into_iter().map(|(k, v)| (k, Box::new(v.into()))).collect())
+    }
+}
+
+impl<T: Into<Json>> From<BTreeMap<String, T>> for Json {
+    fn from(h: BTreeMap<String, T>) -> Self {
+        Json::OBJECT(h.into_iter().map(|(k, v)| (k, Box::new(v.into()))).collect())
+    }
+}
+
+impl<T> TryFrom<Json> for HashMap<String, T>
+    where T: TryFrom<Json, Error = TypeError>
+{
+    type Error = TypeError;
+
+    fn try_from(json: Json) -> Result<Self, TypeError> {
+        match json {
+            Json::OBJECT(h) => h.into_iter().map(|(k, v)| Ok((k, T::try_from(*v)?))).collect(),
+            _ => Err(TypeError::new("object", &json)),
+        }
+    }
+}
+
+impl<T> TryFrom<Json> for BTreeMap<String, T>
+    where T: TryFrom<Json, Error = TypeError>
+{
+    type Error = TypeError;
+
+    fn try_from(json: Json) -> Result<Self, TypeError> {
+        match json {
+            Json::OBJECT(h) => h.into_iter().map(|(k, v)| Ok((k, T::try_from(*v)?))).collect(),
+            _ => Err(TypeError::new("object", &json)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from() {
+        let mut h = HashMap::new();
+        h.insert("a".to_string(), vec![Some(1u8), None]);
+        let mut b = BTreeMap::new();
+        b.insert("x".to_string(), -2.5f32);
+        let json = Json::from(vec![Json::from(h), Json::from(b), Json::from(&["s", "t"][..]), Json::from(u64::MAX)]);
+        assert_eq!(json, crate::parse(r#"[{"a": [1, null]}, {"x": -2.5}, ["s", "t"], 18446744073709551615]"#).unwrap());
+        assert_eq!(Json::from(true), Json::TRUE);
+        assert_eq!(Json::from(None::<String>), Json::NULL);
+    }
+
+    #[test]
+    fn test_try_from() {
+        let json = crate::parse(r#"{"a": [1, null], "b": [2]}"#).unwrap();
+        let h = HashMap::<String, Vec<Option<i32>>>::try_from(json).unwrap();
+        assert_eq!(h["a"], vec![Some(1), None]);
+        let b = BTreeMap::<String, Vec<u8>>::try_from(crate::parse(r#"{"b": [2, 3]}"#).unwrap()).unwrap();
+        assert_eq!(b["b"], vec![2, 3]);
+        assert_eq!(String::try_from(Json::from("s")).unwrap(), "s");
+        assert_eq!(i64::try_from(Json::from(-7)).unwrap(), -7);
+        assert_eq!(f32::try_from(Json::from(0.5)).unwrap(), 0.5);
+
+        let err = |r: Result<(), TypeError>| r.unwrap_err().to_string();
+        assert_eq!(err(u8::try_from(Json::from(300)).map(drop)), "expected u8, found number 300");
+        assert_eq!(err(i32::try_from(Json::from(1.5)).map(drop)), "expected i32, found number 1.5");
+        assert_eq!(err(i64::try_from(Json::from(2f64.powi(63))).map(drop)), "expected i64, found number 9223372036854776000");
+        assert_eq!(i64::try_from(Json::from(-(2f64.powi(63)))).unwrap(), i64::MIN);
+        assert_eq!(u128::try_from(Json::from(2f64.powi(127))).unwrap(), 1 << 127);
+        assert_eq!(i128::try_from(Json::from(-(2f64.powi(127)))).unwrap(), i128::MIN);
+        assert_eq!(Json::from(u128::MAX), Json::NUMBER(2f64.powi(128)));
+        assert!(u128::try_from(Json::from(2f64.powi(128))).is_err());
+        assert!(i128::try_from(Json::from(2f64.powi(127))).is_err());
+        assert_eq!(Option::<u128>::try_from(Json::NULL).unwrap(), None);
+        assert_eq!(err(bool::try_from(Json::NULL).map(drop)), "expected boolean, found null");
+        assert_eq!(err(Vec::<f64>::try_from(Json::from(vec!["x"])).map(drop)), "expected f64, found string");
+        assert_eq!(err(String::try_from(Json::from(Vec::<i32>::new())).map(drop)), "expected string, found array");
+    }
+}
@@ -0,0 +1,590 @@
+// 无损的具体语法树（CST）：保留所有空白、注释以及数字和字符串原来的写法，
+// 原样输出时和输入逐字节相同，适合修改手写的配置文件
+//
+// 每个元素记录它前面的空白（before）和它后面、逗号前面的空白（after），逗号本身不存，
+// 最后一个元素之后、右括号之前的空白记在容器的 end 里。这样增删元素时只要维护 Vec，
+// 逗号在输出时自动补上，不会出现多余或缺少的逗号
+// 逗号后面同一行的注释（tail）属于前一个元素，删除元素时跟着一起删掉
+//
+// 注释按 JSONC 的规则允许出现在任何可以有空白的地方，其余语法和 parse 一样严格；
+// 字符串、数字和字面量直接用 ByteParser 的代码切分，注释和 Lexer 共用 comment_end
+// 新写入的值按紧凑格式输出，新成员的缩进照抄同一个容器里最后一个成员；
+// 空容器里的第一个成员比右括号多缩进一层，一层有多宽、冒号两边怎么留空白都从文档里别的成员推出来
+
+use std::fmt;
+
+use crate::lexer::comment_end;
+use crate::{write_escaped_str, ByteParser, Json, ParseError, ParseOptions};
+
+/// A lossless syntax tree that prints back exactly the text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+    before: String,
+    value: Node,
+    after: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    // 原来的写法和解析出来的值
+    Scalar(String, Json),
+    Array(Container),
+    Object(Container),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Container {
+    items: Vec<Item>,
+    end: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    before: String,
+    key: Option<Key>,
+    value: Node,
+    after: String,
+    tail: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Key {
+    raw: String,
+    name: String,
+    before_colon: String,
+    after_colon: String,
+}
+
+// 从文档里推出来的格式，给空容器里的新成员用
+struct Style {
+    indent: String,
+    colon: Option<(String, String)>,
+}
+
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+struct CstParser<'a> {
+    src: &'a str,
+    pos: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> CstParser<'a> {
+    fn new(src: &'a str) -> Self {
+        CstParser {
+            src,
+            pos: 0,
+            depth: 0,
+            max_depth: ParseOptions::default().max_depth,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn trivia(&mut self) -> Result<String, &'static str> {
+        self.trivia_lines().map(|(t, _)| t)
+    }
+
+    // 同时返回注释以外第一个换行的位置
+    fn trivia_lines(&mut self) -> Result<(String, Option<usize>), &'static str> {
+        let start = self.pos;
+        let mut newline = None;
+        loop {
+            match self.peek() {
+                Some(b'\n') => {
+                    newline.get_or_insert(self.pos - start);
+                    self.pos += 1;
+                }
+                Some(b' ') | Some(b'\r') | Some(b'\t') => self.pos += 1,
+                Some(b'/') => self.pos = comment_end(self.src.as_bytes(), self.pos)?,
+                _ => return Ok((self.src[start..self.pos].to_string(), newline)),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Node, &'static str> {
+        match self.peek() {
+            Some(b'[') => self.nested(false).map(Node::Array),
+            Some(b'{') => self.nested(true).map(Node::Object),
+            _ => {
+                let (raw, v) = self.scalar()?;
+                Ok(Node::Scalar(raw, v))
+            }
+        }
+    }
+
+    // 字符串、数字和字面量的切分和检查都交给 ByteParser，返回原来的写法和值
+    fn scalar(&mut self) -> Result<(String, Json), &'static str> {
+        let (v, len) = ByteParser::scalar(&self.src.as_bytes()[self.pos..])?;
+        let raw = self.src[self.pos..self.pos + len].to_string();
+        self.pos += len;
+        Ok((raw, v))
+    }
+
+    fn nested(&mut self, object: bool) -> Result<Container, &'static str> {
+        if self.depth >= self.max_depth {
+            return Err("parse nesting too deep");
+        }
+        self.depth += 1;
+        let res = self.container(object);
+        self.depth -= 1;
+        res
+    }
+
+    fn container(&mut self, object: bool) -> Result<Container, &'static str> {
+        let close = if object { b'}' } else { b']' };
+        let mut items: Vec<Item> = vec![];
+        self.pos += 1;
+        loop {
+            let (mut before, newline) = self.trivia_lines()?;
+            if let (Some(prev), Some(i)) = (items.last_mut(), newline) {
+                prev.tail = before[..i].to_string();
+                before.replace_range(..i, "");
+            }
+            if items.is_empty() && self.peek() == Some(close) {
+                self.pos += 1;
+                return Ok(Container { items, end: before });
+            }
+            let key = if object { Some(self.key()?) } else { None };
+            let value = self.value()?;
+            let after = self.trivia()?;
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    items.push(Item {
+                        before,
+                        key,
+                        value,
+                        after,
+                        tail: String::new(),
+                    });
+                }
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    items.push(Item {
+                        before,
+                        key,
+                        value,
+                        after: String::new(),
+                        tail: String::new(),
+                    });
+                    return Ok(Container { items, end: after });
+                }
+                _ if object => return Err("parse invalid object"),
+                _ => return Err("parse invalid array"),
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<Key, &'static str> {
+        if self.peek() != Some(b'"') {
+            return Err("parse miss key");
+        }
+        let (raw, name) = match self.scalar()? {
+            (raw, Json::STRING(name)) => (raw, name),
+            _ => return Err("parse miss key"),
+        };
+        let before_colon = self.trivia()?;
+        if self.peek() != Some(b':') {
+            return Err("parse miss colon");
+        }
+        self.pos += 1;
+        let after_colon = self.trivia()?;
+        Ok(Key {
+            raw,
+            name,
+            before_colon,
+            after_colon,
+        })
+    }
+}
+
+/// Parses a JSON text, with optional comments, into a lossless syntax tree.
+pub fn parse_cst(s: &str) -> Result<Cst, ParseError> {
+    let mut parser = CstParser::new(s);
+    // BOM 也当作开头的空白保留下来
+    if s.starts_with('\u{feff}') {
+        parser.pos = '\u{feff}'.len_utf8();
+    }
+    let mut before = s[..parser.pos].to_string();
+    before.push_str(&parser.trivia()?);
+    let value = parser.value()?;
+    let after = parser.trivia()?;
+    if parser.pos < s.len() {
+        return Err(ParseError::Syntax("parse root not singular"));
+    }
+    Ok(Cst { before, value, after })
+}
+
+// 路径的写法和 path_key、path_index 生成的一样：$、.name、["a b"]、[2]
+fn parse_path(path: &str) -> Result<Vec<Step>, &'static str> {
+    let mut parser = CstParser::new(path);
+    if parser.peek() != Some(b'$') {
+        return Err("invalid path");
+    }
+    parser.pos += 1;
+    let mut steps = vec![];
+    while let Some(b) = parser.peek() {
+        parser.pos += 1;
+        match b {
+            b'.' => {
+                let start = parser.pos;
+                let rest = &path[start..];
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err("invalid path");
+                }
+                parser.pos += len;
+                steps.push(Step::Key(rest[..len].to_string()));
+            }
+            b'[' if parser.peek() == Some(b'"') => {
+                match parser.scalar() {
+                    Ok((_, Json::STRING(s))) => steps.push(Step::Key(s)),
+                    _ => return Err("invalid path"),
+                }
+                if parser.peek() != Some(b']') {
+                    return Err("invalid path");
+                }
+                parser.pos += 1;
+            }
+            b'[' => {
+                let rest = &path[parser.pos..];
+                let len = rest.find(']').ok_or("invalid path")?;
+                let i = rest[..len].parse().map_err(|_| "invalid path")?;
+                parser.pos += len + 1;
+                steps.push(Step::Index(i));
+            }
+            _ => return Err("invalid path"),
+        }
+    }
+    Ok(steps)
+}
+
+// 新写入的值用紧凑格式表示
+fn node_from(value: &Json) -> Result<Node, &'static str> {
+    let text = value.to_string();
+    CstParser::new(&text).value().map_err(|_| "invalid value")
+}
+
+impl Node {
+    fn to_json(&self) -> Json {
+        match self {
+            Node::Scalar(_, v) => v.clone(),
+            Node::Array(c) => Json::ARRAY(c.items.iter().map(|item| Box::new(item.value.to_json())).collect()),
+            Node::Object(c) => Json::OBJECT(
+                c.items
+                    .iter()
+                    .filter_map(|item| item.key.as_ref().map(|k| (k.name.clone(), Box::new(item.value.to_json()))))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn find(&self, step: &Step) -> Option<usize> {
+        match (self, step) {
+            (Node::Array(c), Step::Index(i)) if *i < c.items.len() => Some(*i),
+            // 重复的 key 以最后一个为准，和 parse 的结果一致
+            (Node::Object(c), Step::Key(name)) => c.items.iter().rposition(|item| item.key.as_ref().is_some_and(|k| k.name == *name)),
+            _ => None,
+        }
+    }
+
+    fn container_mut(&mut self) -> Option<&mut Container> {
+        match self {
+            Node::Array(c) | Node::Object(c) => Some(c),
+            Node::Scalar(..) => None,
+        }
+    }
+}
+
+impl Style {
+    fn of(node: &Node) -> Self {
+        let mut style = Style {
+            indent: String::new(),
+            colon: None,
+        };
+        style.visit(node);
+        if style.indent.is_empty() {
+            style.indent = String::from("  ");
+        }
+        style
+    }
+
+    fn visit(&mut self, node: &Node) {
+        let c = match node {
+            Node::Array(c) | Node::Object(c) => c,
+            Node::Scalar(..) => return,
+        };
+        if let (Some(first), Some(_)) = (c.items.first(), c.end.rfind('\n')) {
+            let inner = indentation(&first.before);
+            let outer = indentation(&c.end);
+            if self.indent.is_empty() && inner.starts_with('\n') && inner.len() > outer.len() && inner.starts_with(&outer) {
+                self.indent = inner[outer.len()..].to_string();
+            }
+        }
+        for item in &c.items {
+            if let (None, Some(k)) = (&self.colon, &item.key) {
+                self.colon = Some((leading_space(&k.before_colon).to_string(), leading_space(&k.after_colon).to_string()));
+            }
+            self.visit(&item.value);
+        }
+    }
+}
+
+impl Container {
+    // 新元素照抄最后一个元素的换行、缩进和冒号两边的空白，但不照抄其中的注释
+    fn push(&mut self, key: Option<&str>, value: Node, style: &Style) {
+        let template = self.items.last();
+        let multiline = self.end.contains('\n');
+        let before = match template {
+            Some(t) => indentation(&t.before),
+            None if multiline => indentation(&self.end) + &style.indent,
+            None => leading_space(&self.end).to_string(),
+        };
+        let key = key.map(|name| {
+            let mut raw = String::new();
+            // 写入 String 不会失败
+            write_escaped_str(&mut raw, name).unwrap();
+            let (before_colon, after_colon) = match (template.and_then(|t| t.key.as_ref()), &style.colon) {
+                (Some(k), _) => (leading_space(&k.before_colon).to_string(), leading_space(&k.after_colon).to_string()),
+                (None, Some(colon)) => colon.clone(),
+                (None, None) if multiline => (String::new(), String::from(" ")),
+                (None, None) => (String::new(), String::new()),
+            };
+            Key {
+                raw,
+                name: name.to_string(),
+                before_colon,
+                after_colon,
+            }
+        });
+        self.items.push(Item {
+            before,
+            key,
+            value,
+            after: String::new(),
+            tail: String::new(),
+        });
+    }
+}
+
+fn leading_space(s: &str) -> &str {
+    &s[..s.len() - s.trim_start_matches([' ', '\t']).len()]
+}
+
+// 最后一个换行加上最后一行开头的缩进，没有换行时只取开头的空白
+fn indentation(before: &str) -> String {
+    match before.rfind('\n') {
+        Some(i) => {
+            let newline = if before[..i].ends_with('\r') { "\r\n" } else { "\n" };
+            format!("{}{}", newline, leading_space(&before[i + 1..]))
+        }
+        None => leading_space(before).to_string(),
+    }
+}
+
+impl Cst {
+    /// The value the text represents, the same one `parse` returns.
+    pub fn to_json(&self) -> Json {
+        self.value.to_json()
+    }
+
+    fn node_mut(&mut self, steps: &[Step]) -> Option<&mut Node> {
+        let mut node = &mut self.value;
+        for step in steps {
+            let i = node.find(step)?;
+            node = &mut node.container_mut()?.items[i].value;
+        }
+        Some(node)
+    }
+
+    /// Replaces the value at `path`, or adds the member if the parent object lacks it.
+    pub fn set(&mut self, path: &str, value: &Json) -> Result<(), &'static str> {
+        let steps = parse_path(path)?;
+        let value = node_from(value)?;
+        let style = Style::of(&self.value);
+        let (last, parent) = match steps.split_last() {
+            Some(x) => x,
+            None => {
+                self.value = value;
+                return Ok(());
+            }
+        };
+        let node = self.node_mut(parent).ok_or("path not found")?;
+        match (node.find(last), last) {
+            (Some(i), _) => node.container_mut().unwrap().items[i].value = value,
+            (None, Step::Key(name)) => match node {
+                Node::Object(c) => c.push(Some(name), value, &style),
+                _ => return Err("path not found"),
+            },
+            (None, Step::Index(_)) => return Err("path not found"),
+        }
+        Ok(())
+    }
+
+    /// Appends a value to the array at `path`.
+    pub fn push(&mut self, path: &str, value: &Json) -> Result<(), &'static str> {
+        let steps = parse_path(path)?;
+        let value = node_from(value)?;
+        let style = Style::of(&self.value);
+        match self.node_mut(&steps) {
+            Some(Node::Array(c)) => {
+                c.push(None, value, &style);
+                Ok(())
+            }
+            _ => Err("path not found"),
+        }
+    }
+
+    /// Removes the member or element at `path`.
+    pub fn remove(&mut self, path: &str) -> Result<(), &'static str> {
+        let steps = parse_path(path)?;
+        let (last, parent) = steps.split_last().ok_or("path not found")?;
+        let node = self.node_mut(parent).ok_or("path not found")?;
+        let i = node.find(last).ok_or("path not found")?;
+        let c = node.container_mut().unwrap();
+        let removed = c.items.remove(i);
+        // 删掉的是第一个元素时，下一个元素接过它的缩进，逗号后面的空白跟着删掉，注释留下
+        if i == 0 {
+            if let Some(next) = c.items.first_mut() {
+                next.before = indentation(&removed.before) + next.before.trim_start();
+            }
+        }
+        // 删掉的是最后一个元素时，新的最后一个元素后面不再有逗号，逗号两边的注释挪到右括号前面，只有空白就不要了
+        if i == c.items.len() {
+            if let Some(item) = c.items.last_mut() {
+                let moved = std::mem::take(&mut item.after) + &std::mem::take(&mut item.tail);
+                if !moved.trim().is_empty() {
+                    c.end.insert_str(0, &moved);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (c, open, close) = match self {
+            Node::Scalar(raw, _) => return f.write_str(raw),
+            Node::Array(c) => (c, "[", "]"),
+            Node::Object(c) => (c, "{", "}"),
+        };
+        f.write_str(open)?;
+        for (i, item) in c.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",{}", c.items[i - 1].tail)?;
+            }
+            f.write_str(&item.before)?;
+            if let Some(k) = &item.key {
+                write!(f, "{}{}:{}", k.raw, k.before_colon, k.after_colon)?;
+            }
+            write!(f, "{}{}", item.value, item.after)?;
+        }
+        f.write_str(&c.end)?;
+        f.write_str(close)
+    }
+}
+
+// 输出原来的文本，包括所有空白和注释
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.before, self.value, self.after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\u{feff}// settings\n{\n    \"name\" : \"demo\",  /* drop */\n    \"version\": \"1.0.0\",\n    \"deps\": [ 1.50, 2E3 ],\n    \"x y\": {}\n}\n";
+
+    #[test]
+    fn test_round_trip() {
+        let inputs = [CONFIG, " [ ] ", "\"a\\u0041\"", "{\"a\":[[],{},\"\",-0.0e+1]}"];
+        for input in inputs.iter() {
+            let cst = parse_cst(input).unwrap();
+            assert_eq!(cst.to_string(), *input);
+            assert_eq!(cst.to_json(), crate::parse(input.replace("/* drop */", "").replace("// settings", "").as_str()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut cst = parse_cst(CONFIG).unwrap();
+        cst.set("$.version", &Json::STRING("1.0.1".to_string())).unwrap();
+        cst.set("$.license", &Json::STRING("MIT".to_string())).unwrap();
+        cst.push("$.deps", &Json::NUMBER(3.0)).unwrap();
+        cst.remove("$.name").unwrap();
+        cst.set("$[\"x y\"].z", &Json::NULL).unwrap();
+        let expect = "\u{feff}// settings\n{\n    \"version\": \"1.0.1\",\n    \"deps\": [ 1.50, 2E3, 3 ],\n    \"x y\": {\"z\": null},\n    \"license\": \"MIT\"\n}\n";
+        assert_eq!(cst.to_string(), expect);
+
+        cst.remove("$.license").unwrap();
+        cst.remove("$.deps[0]").unwrap();
+        assert!(cst.to_string().contains("\"deps\": [ 2E3, 3 ],\n    \"x y\": {\"z\": null}\n}"));
+
+        // 行尾注释跟着前一个成员走
+        let mut cst = parse_cst("{\n  \"a\": 1, // one\n  // about b\n  \"b\": 2\n}").unwrap();
+        cst.remove("$.b").unwrap();
+        assert_eq!(cst.to_string(), "{\n  \"a\": 1 // one\n}");
+        let mut cst = parse_cst("{\n  \"a\": 1, // one\n  // about b\n  \"b\": 2\n}").unwrap();
+        cst.remove("$.a").unwrap();
+        assert_eq!(cst.to_string(), "{\n  // about b\n  \"b\": 2\n}");
+        // 新成员只照抄缩进，不照抄前一个成员上方的注释
+        let mut cst = parse_cst("{\"a\":1,\n  // about b\n  \"b\" /* k */ : /* v */ 2\n}").unwrap();
+        cst.set("$.c", &Json::NUMBER(3.0)).unwrap();
+        assert_eq!(cst.to_string(), "{\"a\":1,\n  // about b\n  \"b\" /* k */ : /* v */ 2,\n  \"c\" : 3\n}");
+        let mut cst = parse_cst("[1, /* x */ 2]").unwrap();
+        cst.push("$", &Json::NULL).unwrap();
+        assert_eq!(cst.to_string(), "[1, /* x */ 2, null]");
+        assert_eq!(cst.set("$.deps[5]", &Json::NULL), Err("path not found"));
+        assert_eq!(cst.push("$.version", &Json::NULL), Err("path not found"));
+        assert_eq!(cst.remove("version"), Err("invalid path"));
+    }
+
+    #[test]
+    fn test_edit_layout() {
+        let edit = |s: &str, f: &dyn Fn(&mut Cst)| {
+            let mut cst = parse_cst(s).unwrap();
+            f(&mut cst);
+            cst.to_string()
+        };
+        // 删掉第一个成员时，它后面的逗号和空白一起删掉
+        assert_eq!(edit("{\"a\": 1, \"b\": 2}", &|c| c.remove("$.a").unwrap()), "{\"b\": 2}");
+        assert_eq!(edit("{ \"a\": 1, \"b\": 2 }", &|c| c.remove("$.a").unwrap()), "{ \"b\": 2 }");
+        assert_eq!(edit("[1, /* x */ 2]", &|c| c.remove("$[0]").unwrap()), "[/* x */ 2]");
+        assert_eq!(edit("[\n  1,\n\n  2\n]", &|c| c.remove("$[0]").unwrap()), "[\n  2\n]");
+        // 删掉最后一个成员时，前一个成员逗号前后的空白不留在右括号前面
+        assert_eq!(edit("{\"a\": 1 , \"b\": 2}", &|c| c.remove("$.b").unwrap()), "{\"a\": 1}");
+        assert_eq!(edit("[\n  1,  \n  2\n]", &|c| c.remove("$[1]").unwrap()), "[\n  1\n]");
+        // 空容器里的第一个成员按所在位置缩进
+        assert_eq!(edit("{\n}", &|c| c.set("$.a", &Json::NUMBER(1.0)).unwrap()), "{\n  \"a\": 1\n}");
+        assert_eq!(
+            edit("{\n    \"a\" : [],\n    \"b\": {\n    }\n}", &|c| {
+                c.set("$.b.c", &Json::NULL).unwrap();
+                c.push("$.a", &Json::NUMBER(1.0)).unwrap();
+            }),
+            "{\n    \"a\" : [1],\n    \"b\": {\n        \"c\" : null\n    }\n}"
+        );
+        assert_eq!(edit("[ ]", &|c| c.push("$", &Json::NULL).unwrap()), "[ null ]");
+    }
+
+    #[test]
+    fn test_parse_cst_errors() {
+        let err = |s: &str| parse_cst(s).unwrap_err().to_string();
+        assert_eq!(err("[1, 2 /* x"), "parse unterminated comment");
+        assert_eq!(err("[1, 2,]"), "parse value error");
+        assert_eq!(err("{\"a\" 1}"), "parse miss colon");
+        assert_eq!(err("[tru]"), "parse invalid literal");
+        assert_eq!(err("[1] 2"), "parse root not singular");
+    }
+}
@@ -0,0 +1,116 @@
+// 输入编码检测：先看 BOM，没有 BOM 时按 RFC 4627 第 3 节的方法判断
+// JSON 文本的前两个字符一定是 ASCII，所以根据前 4 个字节里 0 出现的位置就能区分
+//
+//     00 00 00 xx  UTF-32BE
+//     00 xx 00 xx  UTF-16BE
+//     xx 00 00 00  UTF-32LE
+//     xx 00 xx 00  UTF-16LE
+//     xx xx xx xx  UTF-8
+//
+// UTF-16 和 UTF-32 的输入先转码成 UTF-8，再交给 ByteParser
+
+use std::borrow::Cow;
+
+use crate::ParseOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/// Detects the encoding of `bytes`, returning it with the length of the BOM to skip.
+pub fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+        [0xFF, 0xFE, 0, 0, ..] => (Encoding::Utf32Le, 4),
+        [0, 0, 0xFE, 0xFF, ..] => (Encoding::Utf32Be, 4),
+        [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+        [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+        [0, 0, 0, _, ..] => (Encoding::Utf32Be, 0),
+        [_, 0, 0, 0, ..] => (Encoding::Utf32Le, 0),
+        // 只有一个字符的文本，比如 UTF-16 编码的 "1"，只有两个字节
+        [0, _, ..] => (Encoding::Utf16Be, 0),
+        [_, 0, ..] => (Encoding::Utf16Le, 0),
+        _ => (Encoding::Utf8, 0),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_u16: fn([u8; 2]) -> u16) -> Result<String, &'static str> {
+    let chunks = bytes.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return Err("parse invalid utf-16");
+    }
+    let units = chunks.map(|c| to_u16([c[0], c[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|_| "parse invalid utf-16")
+}
+
+fn decode_utf32(bytes: &[u8], to_u32: fn([u8; 4]) -> u32) -> Result<String, &'static str> {
+    let chunks = bytes.chunks_exact(4);
+    if !chunks.remainder().is_empty() {
+        return Err("parse invalid utf-32");
+    }
+    chunks
+        .map(|c| char::from_u32(to_u32([c[0], c[1], c[2], c[3]])).ok_or("parse invalid utf-32"))
+        .collect()
+}
+
+/// Strips any BOM and transcodes `bytes` to UTF-8.
+///
+/// UTF-8 input is returned borrowed and is not validated here; that is left to the parser.
+/// With `utf8_only` set, UTF-16 and UTF-32 input is rejected as RFC 8259 requires.
+pub fn to_utf8<'a>(bytes: &'a [u8], options: &ParseOptions) -> Result<Cow<'a, [u8]>, &'static str> {
+    let (encoding, bom) = detect(bytes);
+    let bytes = &bytes[bom..];
+    if encoding != Encoding::Utf8 && options.utf8_only {
+        return Err("parse input not utf-8");
+    }
+    let s = match encoding {
+        Encoding::Utf8 => return Ok(Cow::Borrowed(bytes)),
+        Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes)?,
+        Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes)?,
+        Encoding::Utf32Le => decode_utf32(bytes, u32::from_le_bytes)?,
+        Encoding::Utf32Be => decode_utf32(bytes, u32::from_be_bytes)?,
+    };
+    Ok(Cow::Owned(s.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_with, Json, ParseError};
+
+    fn utf16le(s: &str, bom: bool) -> Vec<u8> {
+        let mut v = if bom { vec![0xFF, 0xFE] } else { vec![] };
+        v.extend(s.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        v
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(b"\xEF\xBB\xBF{}"), (Encoding::Utf8, 3));
+        assert_eq!(detect(b"\xFF\xFE{\x00"), (Encoding::Utf16Le, 2));
+        assert_eq!(detect(b"\x00{\x00}"), (Encoding::Utf16Be, 0));
+        assert_eq!(detect(b"{\x00\x00\x00"), (Encoding::Utf32Le, 0));
+        assert_eq!(detect(b"\x00\x00\x00{"), (Encoding::Utf32Be, 0));
+        assert_eq!(detect(b"1"), (Encoding::Utf8, 0));
+    }
+
+    #[test]
+    fn test_parse_transcoded() {
+        let expect = parse(r#"{"name": "café 😀"}"#).unwrap();
+        assert_eq!(parse(utf16le("{\"name\": \"caf\u{e9} \u{1F600}\"}", true)).unwrap(), expect);
+        assert_eq!(parse(utf16le("{\"name\": \"caf\u{e9} \u{1F600}\"}", false)).unwrap(), expect);
+        assert_eq!(parse(utf16le("1", false)).unwrap(), Json::NUMBER(1.0));
+        assert_eq!(parse(b"\xEF\xBB\xBF[]").unwrap(), Json::ARRAY(vec![]));
+
+        let strict = ParseOptions { utf8_only: true, ..ParseOptions::default() };
+        assert!(parse_with(b"\xEF\xBB\xBF[]", &strict).is_ok());
+        assert!(matches!(parse_with(utf16le("[]", true), &strict), Err(ParseError::Syntax("parse input not utf-8"))));
+    }
+}
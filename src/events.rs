@@ -0,0 +1,263 @@
+// 拉取式的事件接口：每次调用 next 只解析到下一个事件为止，不构造 Json 树
+// 递归下降的 parse_value 换成了显式的栈，标量仍然交给 Parser 原有的 parse_* 方法解析
+
+use crate::handler::Handler;
+use crate::{Json, ParseError, Parser};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(String),
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+}
+
+enum Container {
+    Array,
+    Object,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    // 期待一个值：文档开头或者对象 key 后面
+    Value,
+    ArrayFirst,
+    ArrayNext,
+    ObjectFirst,
+    ObjectNext,
+    // 根值已经结束，只允许剩下空白
+    End,
+    Done,
+}
+
+pub struct Events<T: Iterator<Item = char>> {
+    parser: Parser<T>,
+    stack: Vec<Container>,
+    state: State,
+}
+
+impl<T> Parser<T>
+    where T: Iterator<Item = char>
+{
+    /// Turns the parser into an iterator of events for a single JSON text.
+    pub fn events(self) -> Events<T> {
+        Events {
+            parser: self,
+            stack: vec![],
+            state: State::Value,
+        }
+    }
+}
+
+impl<T> Events<T>
+    where T: Iterator<Item = char>
+{
+    fn after_value(&mut self) {
+        self.state = match self.stack.last() {
+            Some(Container::Array) => State::ArrayNext,
+            Some(Container::Object) => State::ObjectNext,
+            None => State::End,
+        };
+    }
+
+    fn close(&mut self, event: Event) -> Event {
+        self.parser.next_char();
+        self.stack.pop();
+        self.after_value();
+        event
+    }
+
+    fn open(&mut self, container: Container, state: State, event: Event) -> Result<Event, &'static str> {
+        if self.stack.len() >= self.parser.max_depth {
+            return Err("parse nesting too deep");
+        }
+        self.parser.next_char();
+        self.stack.push(container);
+        self.state = state;
+        Ok(event)
+    }
+
+    fn value(&mut self) -> Result<Event, &'static str> {
+        self.parser.parse_whitespace()?;
+        let event = match self.parser.ch {
+            Some('[') => return self.open(Container::Array, State::ArrayFirst, Event::StartArray),
+            Some('{') => return self.open(Container::Object, State::ObjectFirst, Event::StartObject),
+            _ => match self.parser.parse_value()? {
+                Json::NULL => Event::Null,
+                Json::TRUE => Event::Bool(true),
+                Json::FALSE => Event::Bool(false),
+                Json::NUMBER(n) => Event::Number(n),
+                Json::STRING(s) => Event::String(s),
+                // 容器已经在上面处理过了
+                Json::ARRAY(_) | Json::OBJECT(_) => unreachable!(),
+            },
+        };
+        self.after_value();
+        Ok(event)
+    }
+
+    fn key(&mut self) -> Result<Event, &'static str> {
+        self.parser.parse_whitespace()?;
+        let k = self.parser.parse_key()?;
+        self.parser.parse_whitespace()?;
+        if self.parser.ch != Some(':') {
+            return Err("parse miss colon");
+        }
+        self.parser.next_char();
+        self.state = State::Value;
+        Ok(Event::Key(k))
+    }
+
+    // JSON5 允许容器的最后一个元素后面有逗号
+    fn trailing_comma(&mut self, end: char) -> Result<bool, &'static str> {
+        self.parser.parse_whitespace()?;
+        Ok(self.parser.json5 && self.parser.ch == Some(end))
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, &'static str> {
+        self.parser.parse_whitespace()?;
+        let event = match (self.state, self.parser.ch) {
+            (State::Done, _) => return Ok(None),
+            (State::End, None) => {
+                self.state = State::Done;
+                return Ok(None);
+            }
+            (State::End, Some(_)) => return Err("parse root not singular"),
+            (State::Value, _) => self.value()?,
+            (State::ArrayFirst, Some(']')) => self.close(Event::EndArray),
+            (State::ArrayFirst, _) => self.value()?,
+            (State::ArrayNext, Some(']')) => self.close(Event::EndArray),
+            (State::ArrayNext, Some(',')) => {
+                self.parser.next_char();
+                if self.trailing_comma(']')? {
+                    self.close(Event::EndArray)
+                } else {
+                    self.value()?
+                }
+            }
+            (State::ArrayNext, _) => return Err("parse invalid array"),
+            (State::ObjectFirst, Some('}')) => self.close(Event::EndObject),
+            (State::ObjectFirst, _) => self.key()?,
+            (State::ObjectNext, Some('}')) => self.close(Event::EndObject),
+            (State::ObjectNext, Some(',')) => {
+                self.parser.next_char();
+                if self.trailing_comma('}')? {
+                    self.close(Event::EndObject)
+                } else {
+                    self.key()?
+                }
+            }
+            (State::ObjectNext, _) => return Err("parse invalid object"),
+        };
+        Ok(Some(event))
+    }
+}
+
+impl<T> Iterator for Events<T>
+    where T: Iterator<Item = char>
+{
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                // 出错之后不再继续产生事件
+                self.state = State::Done;
+                Some(Err(ParseError::Syntax(e)))
+            }
+        }
+    }
+}
+
+/// Collects the handled tokens as events, for example from `IncrementalParser`.
+impl Handler for Vec<Event> {
+    fn null(&mut self) -> bool {
+        self.push(Event::Null);
+        true
+    }
+
+    fn bool(&mut self, b: bool) -> bool {
+        self.push(Event::Bool(b));
+        true
+    }
+
+    fn number(&mut self, n: f64) -> bool {
+        self.push(Event::Number(n));
+        true
+    }
+
+    fn string(&mut self, s: String) -> bool {
+        self.push(Event::String(s));
+        true
+    }
+
+    fn start_object(&mut self) -> bool {
+        self.push(Event::StartObject);
+        true
+    }
+
+    fn key(&mut self, k: String) -> bool {
+        self.push(Event::Key(k));
+        true
+    }
+
+    fn end_object(&mut self) -> bool {
+        self.push(Event::EndObject);
+        true
+    }
+
+    fn start_array(&mut self) -> bool {
+        self.push(Event::StartArray);
+        true
+    }
+
+    fn end_array(&mut self) -> bool {
+        self.push(Event::EndArray);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(s: &str) -> Vec<Result<Event, ParseError>> {
+        Parser::new(s.chars()).events().collect()
+    }
+
+    #[test]
+    fn test_events() {
+        let got: Vec<Event> = events(r#" {"a": [1, "x", true, null], "b": {}} "#)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(got, vec![
+            Event::StartObject,
+            Event::Key("a".to_string()),
+            Event::StartArray,
+            Event::Number(1.0),
+            Event::String("x".to_string()),
+            Event::Bool(true),
+            Event::Null,
+            Event::EndArray,
+            Event::Key("b".to_string()),
+            Event::StartObject,
+            Event::EndObject,
+            Event::EndObject,
+        ]);
+    }
+
+    #[test]
+    fn test_events_error() {
+        let got = events("[1 2]");
+        assert_eq!(got.len(), 3);
+        assert!(matches!(got[2], Err(ParseError::Syntax("parse invalid array"))));
+        assert!(matches!(events("1 2").last(), Some(Err(ParseError::Syntax("parse root not singular")))));
+    }
+}
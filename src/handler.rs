@@ -0,0 +1,417 @@
+// SAX 风格的接口：Parser 每解析出一个 token 就调用 Handler 对应的回调
+// 和 RapidJSON 一样，回调返回 false 时立即终止解析
+// 标量仍然由 Parser 原有的 parse_* 方法解析，这里只把数组和对象的循环改成调用回调
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{write_escaped_str, write_number, Json, ParseError, Parser};
+
+pub trait Handler {
+    fn null(&mut self) -> bool;
+    fn bool(&mut self, b: bool) -> bool;
+    fn number(&mut self, n: f64) -> bool;
+    fn string(&mut self, s: String) -> bool;
+    fn start_object(&mut self) -> bool;
+    fn key(&mut self, k: String) -> bool;
+    fn end_object(&mut self) -> bool;
+    fn start_array(&mut self) -> bool;
+    fn end_array(&mut self) -> bool;
+}
+
+pub(crate) fn check(ok: bool) -> Result<(), &'static str> {
+    if ok {
+        Ok(())
+    } else {
+        Err("parse terminated by handler")
+    }
+}
+
+impl<T> Parser<T>
+    where T: Iterator<Item = char>
+{
+    /// Parses a single JSON text, reporting every token to `handler` instead of building a `Json`.
+    pub fn parse_with_handler<H: Handler>(&mut self, handler: &mut H) -> Result<(), ParseError>
+    {
+        self.parse_whitespace()?;
+        self.drive_value(handler)?;
+        self.parse_whitespace()?;
+        if self.ch.is_some() {
+            return Err(ParseError::Syntax("parse root not singular"));
+        }
+        Ok(())
+    }
+
+    fn drive_value<H: Handler>(&mut self, handler: &mut H) -> Result<(), &'static str>
+    {
+        let ok = match self.ch {
+            Some('[') => return self.parse_nested(|p| p.drive_array(handler)),
+            Some('{') => return self.parse_nested(|p| p.drive_object(handler)),
+            _ => match self.parse_value()? {
+                Json::NULL => handler.null(),
+                Json::TRUE => handler.bool(true),
+                Json::FALSE => handler.bool(false),
+                Json::NUMBER(n) => handler.number(n),
+                Json::STRING(s) => handler.string(s),
+                // 容器已经在上面处理过了
+                Json::ARRAY(_) | Json::OBJECT(_) => unreachable!(),
+            },
+        };
+        check(ok)
+    }
+
+    fn drive_array<H: Handler>(&mut self, handler: &mut H) -> Result<(), &'static str>
+    {
+        check(handler.start_array())?;
+        self.next_char();
+        self.parse_whitespace()?;
+        if self.ch == Some(']') {
+            self.next_char();
+            return check(handler.end_array());
+        }
+        loop {
+            self.parse_whitespace()?;
+            self.drive_value(handler)?;
+            self.parse_whitespace()?;
+            match self.ch {
+                Some(']') => {
+                    self.next_char();
+                    return check(handler.end_array());
+                }
+                Some(',') => {
+                    self.next_char();
+                    self.parse_whitespace()?;
+                    if self.json5 && self.ch == Some(']') {
+                        self.next_char();
+                        return check(handler.end_array());
+                    }
+                }
+                _ => return Err("parse invalid array"),
+            }
+        }
+    }
+
+    fn drive_object<H: Handler>(&mut self, handler: &mut H) -> Result<(), &'static str>
+    {
+        check(handler.start_object())?;
+        self.next_char();
+        self.parse_whitespace()?;
+        if self.ch == Some('}') {
+            self.next_char();
+            return check(handler.end_object());
+        }
+        loop {
+            self.parse_whitespace()?;
+            let k = self.parse_key()?;
+            check(handler.key(k))?;
+            self.parse_whitespace()?;
+            if self.ch != Some(':') {
+                return Err("parse miss colon");
+            }
+            self.next_char();
+            self.parse_whitespace()?;
+            self.drive_value(handler)?;
+            self.parse_whitespace()?;
+            match self.ch {
+                Some('}') => {
+                    self.next_char();
+                    return check(handler.end_object());
+                }
+                Some(',') => {
+                    self.next_char();
+                    self.parse_whitespace()?;
+                    if self.json5 && self.ch == Some('}') {
+                        self.next_char();
+                        return check(handler.end_object());
+                    }
+                }
+                _ => return Err("parse invalid object"),
+            }
+        }
+    }
+}
+
+#[allow(clippy::vec_box)]
+enum Frame {
+    Array(Vec<Box<Json>>),
+    Object(HashMap<String, Box<Json>>, Option<String>),
+}
+
+/// Builds a `Json` tree, the same one `parse` returns.
+#[derive(Default)]
+pub struct TreeBuilder {
+    stack: Vec<Frame>,
+    root: Option<Json>,
+}
+
+impl TreeBuilder {
+    pub fn new() -> Self {
+        TreeBuilder::default()
+    }
+
+    /// The parsed value, once a complete JSON text has been handled.
+    pub fn into_json(self) -> Option<Json> {
+        self.root
+    }
+
+    fn push(&mut self, v: Json) -> bool {
+        match self.stack.last_mut() {
+            Some(Frame::Array(arr)) => arr.push(Box::new(v)),
+            Some(Frame::Object(h, k)) => match k.take() {
+                Some(k) => {
+                    h.insert(k, Box::new(v));
+                }
+                None => return false,
+            },
+            None => self.root = Some(v),
+        }
+        true
+    }
+}
+
+impl Handler for TreeBuilder {
+    fn null(&mut self) -> bool {
+        self.push(Json::NULL)
+    }
+
+    fn bool(&mut self, b: bool) -> bool {
+        self.push(if b { Json::TRUE } else { Json::FALSE })
+    }
+
+    fn number(&mut self, n: f64) -> bool {
+        self.push(Json::NUMBER(n))
+    }
+
+    fn string(&mut self, s: String) -> bool {
+        self.push(Json::STRING(s))
+    }
+
+    fn start_object(&mut self) -> bool {
+        self.stack.push(Frame::Object(HashMap::new(), None));
+        true
+    }
+
+    fn key(&mut self, k: String) -> bool {
+        match self.stack.last_mut() {
+            Some(Frame::Object(_, key)) => {
+                *key = Some(k);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn end_object(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(Frame::Object(h, None)) => self.push(Json::OBJECT(h)),
+            _ => false,
+        }
+    }
+
+    fn start_array(&mut self) -> bool {
+        self.stack.push(Frame::Array(vec![]));
+        true
+    }
+
+    fn end_array(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(Frame::Array(arr)) => self.push(Json::ARRAY(arr)),
+            _ => false,
+        }
+    }
+}
+
+/// Writes the handled tokens as compact JSON text, without any whitespace.
+pub struct CompactWriter<W: Write> {
+    out: W,
+    // 每一层是否还没有写过元素，用来决定要不要先写逗号
+    first: Vec<bool>,
+    after_key: bool,
+}
+
+impl<W: Write> CompactWriter<W> {
+    pub fn new(out: W) -> Self {
+        CompactWriter {
+            out,
+            first: vec![],
+            after_key: false,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn separator(&mut self) -> bool {
+        if self.after_key {
+            self.after_key = false;
+            return true;
+        }
+        match self.first.last_mut() {
+            Some(first) if *first => {
+                *first = false;
+                true
+            }
+            Some(_) => self.out.write_char(',').is_ok(),
+            None => true,
+        }
+    }
+}
+
+impl<W: Write> Handler for CompactWriter<W> {
+    fn null(&mut self) -> bool {
+        self.separator() && self.out.write_str("null").is_ok()
+    }
+
+    fn bool(&mut self, b: bool) -> bool {
+        self.separator() && self.out.write_str(if b { "true" } else { "false" }).is_ok()
+    }
+
+    fn number(&mut self, n: f64) -> bool {
+        self.separator() && write_number(&mut self.out, n).is_ok()
+    }
+
+    fn string(&mut self, s: String) -> bool {
+        self.separator() && write_escaped_str(&mut self.out, &s).is_ok()
+    }
+
+    fn start_object(&mut self) -> bool {
+        let ok = self.separator() && self.out.write_char('{').is_ok();
+        self.first.push(true);
+        ok
+    }
+
+    fn key(&mut self, k: String) -> bool {
+        let ok = self.separator() && write_escaped_str(&mut self.out, &k).is_ok() && self.out.write_char(':').is_ok();
+        self.after_key = true;
+        ok
+    }
+
+    fn end_object(&mut self) -> bool {
+        self.first.pop();
+        self.out.write_char('}').is_ok()
+    }
+
+    fn start_array(&mut self) -> bool {
+        let ok = self.separator() && self.out.write_char('[').is_ok();
+        self.first.push(true);
+        ok
+    }
+
+    fn end_array(&mut self) -> bool {
+        self.first.pop();
+        self.out.write_char(']').is_ok()
+    }
+}
+
+/// Counts the tokens of each kind and the deepest nesting seen.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub objects: usize,
+    pub arrays: usize,
+    pub keys: usize,
+    pub strings: usize,
+    pub numbers: usize,
+    pub bools: usize,
+    pub nulls: usize,
+    pub max_depth: usize,
+    depth: usize,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats::default()
+    }
+
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+}
+
+impl Handler for Stats {
+    fn null(&mut self) -> bool {
+        self.nulls += 1;
+        true
+    }
+
+    fn bool(&mut self, _: bool) -> bool {
+        self.bools += 1;
+        true
+    }
+
+    fn number(&mut self, _: f64) -> bool {
+        self.numbers += 1;
+        true
+    }
+
+    fn string(&mut self, _: String) -> bool {
+        self.strings += 1;
+        true
+    }
+
+    fn start_object(&mut self) -> bool {
+        self.objects += 1;
+        self.enter();
+        true
+    }
+
+    fn key(&mut self, _: String) -> bool {
+        self.keys += 1;
+        true
+    }
+
+    fn end_object(&mut self) -> bool {
+        self.depth -= 1;
+        true
+    }
+
+    fn start_array(&mut self) -> bool {
+        self.arrays += 1;
+        self.enter();
+        true
+    }
+
+    fn end_array(&mut self) -> bool {
+        self.depth -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#" { "a" : [1, "x\n", true, null, []], "b" : {} } "#;
+
+    #[test]
+    fn test_tree_builder() {
+        let mut builder = TreeBuilder::new();
+        Parser::new(INPUT.chars()).parse_with_handler(&mut builder).unwrap();
+        assert_eq!(builder.into_json().unwrap(), crate::parse(INPUT).unwrap());
+    }
+
+    #[test]
+    fn test_compact_writer() {
+        let mut writer = CompactWriter::new(String::new());
+        Parser::new("[1, {\"k\": [true, {}]}, \"s\"]".chars()).parse_with_handler(&mut writer).unwrap();
+        assert_eq!(writer.into_inner(), r#"[1,{"k":[true,{}]},"s"]"#);
+
+        let json5 = crate::ParseOptions {
+            json5: true,
+            ..crate::ParseOptions::default()
+        };
+        let mut writer = CompactWriter::new(String::new());
+        Parser::with_options("[NaN, -Infinity]".chars(), &json5).parse_with_handler(&mut writer).unwrap();
+        assert_eq!(writer.into_inner(), "[null,null]");
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut stats = Stats::new();
+        Parser::new(INPUT.chars()).parse_with_handler(&mut stats).unwrap();
+        assert_eq!((stats.objects, stats.arrays, stats.keys), (2, 2, 2));
+        assert_eq!((stats.strings, stats.numbers, stats.bools, stats.nulls), (1, 1, 1, 1));
+        assert_eq!(stats.max_depth, 3);
+    }
+}
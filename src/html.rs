@@ -0,0 +1,216 @@
+// 生成带语法高亮的 HTML，每个 token 放在一个 <span class="json-*"> 里，样式交给页面的 CSS：
+// json-key、json-string、json-number、json-boolean、json-null、json-punctuation、json-comment
+//
+// highlight_html 用 Lexer 逐个 token 输出，保留原来的空白和注释
+// to_html 按 color::write_json 的格式缩进输出一个 Json 值，可以选择把每个非空的数组和对象
+// 放进 <details>，这时每一行都是一个块级元素，折叠后只显示开头的一行
+// <pre> 里只能放行内元素，所以可折叠的输出外层换成 <div class="json">，用 white-space: pre 保留缩进
+
+use std::fmt::Write;
+
+use crate::{write_escaped_str, Json, Lexer, ParseError, ParseOptions, TokenKind};
+
+fn escape_html(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn span(out: &mut String, class: &str, text: &str) {
+    write!(out, "<span class=\"json-{}\">", class).unwrap();
+    escape_html(out, text);
+    out.push_str("</span>");
+}
+
+/// Highlights a JSON text token by token, keeping its whitespace and comments as they are.
+pub fn highlight_html(src: &str, options: &ParseOptions) -> Result<String, ParseError> {
+    let tokens = Lexer::with_options(src, options).collect::<Result<Vec<_>, _>>()?;
+    let mut out = String::from("<pre class=\"json\">");
+    for (i, token) in tokens.iter().enumerate() {
+        let class = match token.kind {
+            TokenKind::Whitespace => {
+                escape_html(&mut out, token.text);
+                continue;
+            }
+            // 后面紧跟冒号的字符串是 key
+            TokenKind::String => {
+                let next = tokens[i + 1..].iter().find(|t| !matches!(t.kind, TokenKind::Whitespace | TokenKind::Comment));
+                if next.is_some_and(|t| t.text == ":") {
+                    "key"
+                } else {
+                    "string"
+                }
+            }
+            TokenKind::Number => "number",
+            TokenKind::Literal if token.text == "null" => "null",
+            TokenKind::Literal => "boolean",
+            TokenKind::Punctuation => "punctuation",
+            TokenKind::Comment => "comment",
+        };
+        span(&mut out, class, token.text);
+    }
+    out.push_str("</pre>");
+    Ok(out)
+}
+
+/// Renders `json` pretty-printed as highlighted HTML, optionally with collapsible arrays and objects.
+///
+/// The result is wrapped in `<pre class="json">`, or in `<div class="json">` with
+/// `white-space: pre` when collapsible, since `<details>` may not appear inside `<pre>`.
+pub fn to_html(json: &Json, collapsible: bool) -> String {
+    let open = if collapsible {
+        "<div class=\"json\" style=\"white-space: pre\">"
+    } else {
+        "<pre class=\"json\">"
+    };
+    let mut html = HtmlWriter {
+        out: String::from(open),
+        collapsible,
+    };
+    html.write_value(json, 0, String::new(), "");
+    if collapsible {
+        html.out.push_str("</div>");
+    } else {
+        html.out.pop();
+        html.out.push_str("</pre>");
+    }
+    html.out
+}
+
+struct HtmlWriter {
+    out: String,
+    collapsible: bool,
+}
+
+impl HtmlWriter {
+    fn line(&mut self, content: &str) {
+        if self.collapsible {
+            write!(self.out, "<div>{}</div>", content).unwrap();
+        } else {
+            writeln!(self.out, "{}", content).unwrap();
+        }
+    }
+
+    // prefix 是这一行值前面的缩进和 key，suffix 是值后面的逗号，都已经是 HTML
+    fn write_value(&mut self, json: &Json, depth: usize, mut prefix: String, suffix: &str) {
+        let mut text = String::new();
+        let (class, open, close) = match json {
+            Json::NULL => ("null", "", ""),
+            Json::TRUE | Json::FALSE => ("boolean", "", ""),
+            // NaN 和无穷大输出成 null
+            Json::NUMBER(n) if !n.is_finite() => ("null", "", ""),
+            Json::NUMBER(_) => ("number", "", ""),
+            Json::STRING(s) => {
+                write_escaped_str(&mut text, s).unwrap();
+                ("string", "", "")
+            }
+            Json::ARRAY(arr) if arr.is_empty() => ("punctuation", "", ""),
+            Json::OBJECT(h) if h.is_empty() => ("punctuation", "", ""),
+            Json::ARRAY(_) => ("", "[", "]"),
+            Json::OBJECT(_) => ("", "{", "}"),
+        };
+        if open.is_empty() {
+            if text.is_empty() {
+                text = json.to_string();
+            }
+            span(&mut prefix, class, &text);
+            prefix.push_str(suffix);
+            self.line(&prefix);
+            return;
+        }
+        span(&mut prefix, "punctuation", open);
+        if self.collapsible {
+            write!(self.out, "<details open><summary>{}</summary>", prefix).unwrap();
+        } else {
+            self.line(&prefix);
+        }
+        let indent = "  ".repeat(depth + 1);
+        let mut comma = String::new();
+        span(&mut comma, "punctuation", ",");
+        match json {
+            Json::ARRAY(arr) => {
+                for (i, v) in arr.iter().enumerate() {
+                    let last = i + 1 == arr.len();
+                    self.write_value(v, depth + 1, indent.clone(), if last { "" } else { &comma });
+                }
+            }
+            Json::OBJECT(h) => {
+                for (i, (k, v)) in h.iter().enumerate() {
+                    let mut key = String::new();
+                    write_escaped_str(&mut key, k).unwrap();
+                    let mut child = indent.clone();
+                    span(&mut child, "key", &key);
+                    span(&mut child, "punctuation", ":");
+                    child.push(' ');
+                    let last = i + 1 == h.len();
+                    self.write_value(v, depth + 1, child, if last { "" } else { &comma });
+                }
+            }
+            _ => {}
+        }
+        let mut end = "  ".repeat(depth);
+        span(&mut end, "punctuation", close);
+        end.push_str(suffix);
+        self.line(&end);
+        if self.collapsible {
+            self.out.push_str("</details>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_html() {
+        let options = ParseOptions {
+            jsonc: true,
+            ..ParseOptions::default()
+        };
+        let html = highlight_html("{\"a<b\": [1, null] // x & y\n}", &options).unwrap();
+        assert_eq!(
+            html,
+            "<pre class=\"json\"><span class=\"json-punctuation\">{</span><span class=\"json-key\">&quot;a&lt;b&quot;</span>\
+             <span class=\"json-punctuation\">:</span> <span class=\"json-punctuation\">[</span>\
+             <span class=\"json-number\">1</span><span class=\"json-punctuation\">,</span> \
+             <span class=\"json-null\">null</span><span class=\"json-punctuation\">]</span> \
+             <span class=\"json-comment\">// x &amp; y</span>\n<span class=\"json-punctuation\">}</span></pre>"
+        );
+        assert!(highlight_html("[1, 2", &ParseOptions::default()).is_ok());
+        assert!(highlight_html("[1, x]", &ParseOptions::default()).is_err());
+        assert_eq!(
+            highlight_html("\u{feff}1", &ParseOptions::default()).unwrap(),
+            "<pre class=\"json\">\u{feff}<span class=\"json-number\">1</span></pre>"
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let json = crate::parse("{\"a\": [true, \"<\"]}").unwrap();
+        let p = |s: &str| format!("<span class=\"json-punctuation\">{}</span>", s);
+        let key = "<span class=\"json-key\">&quot;a&quot;</span>";
+        let t = "<span class=\"json-boolean\">true</span>";
+        let s = "<span class=\"json-string\">&quot;&lt;&quot;</span>";
+
+        let expect = format!(
+            "<pre class=\"json\">{}\n  {}{} {}\n    {}{}\n    {}\n  {}\n{}</pre>",
+            p("{"), key, p(":"), p("["), t, p(","), s, p("]"), p("}")
+        );
+        assert_eq!(to_html(&json, false), expect);
+
+        let expect = format!(
+            "<div class=\"json\" style=\"white-space: pre\"><details open><summary>{}</summary><details open><summary>  {}{} {}</summary>\
+             <div>    {}{}</div><div>    {}</div><div>  {}</div></details><div>{}</div></details></div>",
+            p("{"), key, p(":"), p("["), t, p(","), s, p("]"), p("}")
+        );
+        assert_eq!(to_html(&json, true), expect);
+        assert_eq!(to_html(&Json::ARRAY(vec![]), true), format!("<div class=\"json\" style=\"white-space: pre\"><div>{}</div></div>", p("[]")));
+    }
+}
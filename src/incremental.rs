@@ -0,0 +1,615 @@
+// 推送式的增量解析：网络代码每收到一块数据就调用 feed，不需要阻塞等待完整的输入
+//
+// 和 Events 一样用显式的栈代替递归，数组和对象的开始和结束、每个 key 和每个标量一完成
+// 就交给 Handler，不用等整个文本到齐；用 TreeBuilder 作为 Handler 就得到和 parse 相同的 Json
+// 字符串、数字、字面量和 key 要等整个 token 到齐（闭合的引号或者后面的分隔符）再交给
+// ByteParser（JSON5 时是按字符解析的 Parser）原有的代码解析，所以切分的位置和错误都和 parse 一致
+// 其余的状态只有括号、逗号、冒号、注释和栈，数据在任何位置被切开都没有关系
+//
+// 和 parse_with 一样，输入是一个完整的 JSON 文本，根值后面只能有空白和注释，
+// 否则报告 parse root not singular；连续的多个值请用 parse_stream
+// 开头最多 4 个字节用来检测编码，UTF-16 和 UTF-32 的输入和 from_reader 一样等到 finish 时整个转码
+
+use std::str;
+
+use crate::encoding::{self, Encoding};
+use crate::handler::{check, Handler};
+use crate::input::parse_chars;
+use crate::{ByteParser, Json, ParseError, ParseOptions};
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    // 期待一个值：文档开头、冒号后面或者标准 JSON 数组的逗号后面
+    Value,
+    ArrayFirst,
+    ArrayNext,
+    // JSON5 数组的逗号后面，也可以直接是右括号
+    ArrayComma,
+    ObjectFirst,
+    ObjectNext,
+    ObjectComma,
+    Key,
+    // key 已经解析出来，等冒号之后再交给 Handler
+    Colon,
+    // 根值已经结束，只允许剩下空白和注释
+    End,
+}
+
+enum Container {
+    Array,
+    Object,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Comment {
+    // 刚读到开头的 /
+    Slash,
+    Line,
+    Block,
+    // 块注释里刚读到 *
+    BlockStar,
+}
+
+// 还没有到齐的字符串、数字、字面量或者 key
+#[derive(Clone, Copy)]
+struct Token {
+    start: usize,
+    // 字符串的引号，数字、字面量和 JSON5 的标识符是 None
+    quote: Option<u8>,
+    escape: bool,
+}
+
+/// Parses a single JSON text from chunks of bytes as they arrive, reporting each token to a `Handler`.
+///
+/// The tokens and the error are the same however the input is split into chunks, and the same as
+/// `Events` and `parse_with` give for the whole text: trailing input after the root value is
+/// "parse root not singular". Tokens before an error have already been reported when it is returned.
+pub struct IncrementalParser {
+    options: ParseOptions,
+    comments: bool,
+    // 编码确定之前的字节，不是 UTF-8 时整个输入都留在这里
+    head: Vec<u8>,
+    utf8: bool,
+    buf: Vec<u8>,
+    pos: usize,
+    state: State,
+    stack: Vec<Container>,
+    key: Option<String>,
+    token: Option<Token>,
+    comment: Option<Comment>,
+    error: Option<&'static str>,
+}
+
+impl Default for IncrementalParser {
+    fn default() -> Self {
+        IncrementalParser::new()
+    }
+}
+
+impl IncrementalParser {
+    pub fn new() -> Self {
+        IncrementalParser::with_options(&ParseOptions::default())
+    }
+
+    pub fn with_options(options: &ParseOptions) -> Self {
+        IncrementalParser {
+            options: options.clone(),
+            comments: options.json5 || options.jsonc,
+            head: vec![],
+            utf8: false,
+            buf: vec![],
+            pos: 0,
+            state: State::Value,
+            stack: vec![],
+            key: None,
+            token: None,
+            comment: None,
+            error: None,
+        }
+    }
+
+    /// Feeds the next chunk of input, reporting every token it completes to `handler`.
+    ///
+    /// After an error the parser is stuck and every later call returns the same error.
+    pub fn feed<H: Handler>(&mut self, chunk: &[u8], handler: &mut H) -> Result<(), ParseError> {
+        let res = self.push(chunk, false, handler);
+        self.fail(res)
+    }
+
+    /// Signals the end of input, reporting the tokens it completes, such as a trailing number.
+    pub fn finish<H: Handler>(mut self, handler: &mut H) -> Result<(), ParseError> {
+        let res = self.push(&[], true, handler);
+        self.fail(res)
+    }
+
+    fn fail(&mut self, res: Result<(), &'static str>) -> Result<(), ParseError> {
+        if let Err(e) = res {
+            self.error = Some(e);
+        }
+        res.map_err(ParseError::Syntax)
+    }
+
+    fn push<H: Handler>(&mut self, chunk: &[u8], eof: bool, handler: &mut H) -> Result<(), &'static str> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        if self.utf8 {
+            self.buf.extend_from_slice(chunk);
+        } else {
+            self.head.extend_from_slice(chunk);
+            self.decode_head(eof)?;
+            if !self.utf8 {
+                return Ok(());
+            }
+        }
+        self.scan(eof, handler)?;
+        // 丢掉已经处理完的字节，只留下还没到齐的 token
+        let keep = self.token.map_or(self.pos, |t| t.start);
+        self.buf.drain(..keep);
+        self.pos -= keep;
+        if let Some(t) = &mut self.token {
+            t.start -= keep;
+        }
+        Ok(())
+    }
+
+    // 编码要看开头最多 4 个字节才能确定，UTF-8 去掉 BOM 后开始解析，其它编码等到输入结束再转码
+    fn decode_head(&mut self, eof: bool) -> Result<(), &'static str> {
+        if self.head.len() < 4 && !eof {
+            return Ok(());
+        }
+        let (enc, bom) = encoding::detect(&self.head);
+        if enc == Encoding::Utf8 {
+            self.buf.extend_from_slice(&self.head[bom..]);
+        } else if eof || self.options.utf8_only {
+            // utf8_only 时 to_utf8 直接报错
+            let bytes = encoding::to_utf8(&self.head, &self.options)?;
+            self.buf.extend_from_slice(&bytes);
+        } else {
+            return Ok(());
+        }
+        self.head = vec![];
+        self.utf8 = true;
+        Ok(())
+    }
+
+    fn scan<H: Handler>(&mut self, eof: bool, handler: &mut H) -> Result<(), &'static str> {
+        loop {
+            if self.token.is_some() {
+                match self.token_end(eof) {
+                    Some(end) => self.complete(end, handler)?,
+                    None => return Ok(()),
+                }
+                continue;
+            }
+            let b = match self.buf.get(self.pos) {
+                Some(&b) => b,
+                None if eof => return self.end_of_input(),
+                None => return Ok(()),
+            };
+            let (width, space) = match self.peek_char(eof)? {
+                Some(c) => c,
+                None => return Ok(()),
+            };
+            if let Some(c) = self.comment {
+                self.comment = step_comment(c, b)?;
+                self.pos += width;
+            } else if space {
+                self.pos += width;
+            } else if self.comments && b == b'/' {
+                self.comment = Some(Comment::Slash);
+                self.pos += 1;
+            } else {
+                self.dispatch(b, handler)?;
+            }
+        }
+    }
+
+    // 当前位置的字符的长度和它是不是空白，字节还没到齐时返回 None
+    // 标准 JSON 和 JSONC 的空白只有四个 ASCII 字符，字符串之外的非 ASCII 字节一定是语法错误，不用解码
+    // JSON5 按字符解析，空白还包括 Unicode 空格和 BOM，所以要先凑齐一个字符并检查 UTF-8
+    fn peek_char(&self, eof: bool) -> Result<Option<(usize, bool)>, &'static str> {
+        let b = self.buf[self.pos];
+        if !self.options.json5 {
+            return Ok(Some((1, is_whitespace(b))));
+        }
+        let width = match b {
+            0x00..=0x7F => return Ok(Some((1, (b as char).is_whitespace()))),
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Err("parse invalid utf-8"),
+        };
+        let bytes = match self.buf.get(self.pos..self.pos + width) {
+            Some(bytes) => bytes,
+            None if eof => return Err("parse invalid utf-8"),
+            None => return Ok(None),
+        };
+        let c = str::from_utf8(bytes).map_err(|_| "parse invalid utf-8")?.chars().next().unwrap();
+        Ok(Some((width, c.is_whitespace() || c == '\u{feff}')))
+    }
+
+    fn dispatch<H: Handler>(&mut self, b: u8, handler: &mut H) -> Result<(), &'static str> {
+        let json5 = self.options.json5;
+        match (self.state, b) {
+            (State::End, _) => Err("parse root not singular"),
+            (State::ArrayFirst, b']') | (State::ArrayNext, b']') | (State::ArrayComma, b']') => {
+                self.close(handler.end_array())
+            }
+            (State::ObjectFirst, b'}') | (State::ObjectNext, b'}') | (State::ObjectComma, b'}') => {
+                self.close(handler.end_object())
+            }
+            (State::ArrayNext, b',') => {
+                self.pos += 1;
+                self.state = if json5 { State::ArrayComma } else { State::Value };
+                Ok(())
+            }
+            (State::ArrayNext, _) => Err("parse invalid array"),
+            (State::ObjectNext, b',') => {
+                self.pos += 1;
+                self.state = if json5 { State::ObjectComma } else { State::Key };
+                Ok(())
+            }
+            (State::ObjectNext, _) => Err("parse invalid object"),
+            (State::Colon, b':') => {
+                self.pos += 1;
+                self.state = State::Value;
+                check(handler.key(self.key.take().unwrap()))
+            }
+            (State::Colon, _) => Err("parse miss colon"),
+            (State::ObjectFirst, _) | (State::Key, _) | (State::ObjectComma, _) => {
+                match b {
+                    b'"' => self.start_token(Some(b)),
+                    b'\'' if json5 => self.start_token(Some(b)),
+                    // JSON5 的标识符由 Parser::parse_key 检查，这里只排除结构字符
+                    _ if json5 && !self.is_delimiter(b) => self.start_token(None),
+                    _ => return Err("parse miss key"),
+                }
+                Ok(())
+            }
+            (_, b'[') => self.open(Container::Array, handler),
+            (_, b'{') => self.open(Container::Object, handler),
+            (_, b']') | (_, b'}') | (_, b',') | (_, b':') => Err("parse value error"),
+            (_, b'"') => {
+                self.start_token(Some(b));
+                Ok(())
+            }
+            (_, b'\'') if json5 => {
+                self.start_token(Some(b));
+                Ok(())
+            }
+            _ => {
+                self.start_token(None);
+                Ok(())
+            }
+        }
+    }
+
+    fn open<H: Handler>(&mut self, container: Container, handler: &mut H) -> Result<(), &'static str> {
+        if self.stack.len() >= self.options.max_depth {
+            return Err("parse nesting too deep");
+        }
+        self.state = match container {
+            Container::Array => {
+                check(handler.start_array())?;
+                State::ArrayFirst
+            }
+            Container::Object => {
+                check(handler.start_object())?;
+                State::ObjectFirst
+            }
+        };
+        self.pos += 1;
+        self.stack.push(container);
+        Ok(())
+    }
+
+    fn close(&mut self, ok: bool) -> Result<(), &'static str> {
+        check(ok)?;
+        self.pos += 1;
+        self.stack.pop();
+        self.after_value();
+        Ok(())
+    }
+
+    fn after_value(&mut self) {
+        self.state = match self.stack.last() {
+            Some(Container::Array) => State::ArrayNext,
+            Some(Container::Object) => State::ObjectNext,
+            None => State::End,
+        };
+    }
+
+    fn start_token(&mut self, quote: Option<u8>) {
+        self.token = Some(Token {
+            start: self.pos,
+            quote,
+            escape: false,
+        });
+        if quote.is_some() {
+            self.pos += 1;
+        }
+    }
+
+    // 数字、字面量和标识符里不会出现的字符，它们标志着 token 的结束
+    fn is_delimiter(&self, b: u8) -> bool {
+        match b {
+            b'[' | b']' | b'{' | b'}' | b',' | b':' | b'"' => true,
+            b'\'' => self.options.json5,
+            b'/' => self.comments,
+            _ if self.options.json5 => (b as char).is_whitespace(),
+            _ => is_whitespace(b),
+        }
+    }
+
+    // 向前扫描当前的 token，返回它的结束位置；需要更多输入时返回 None
+    // 字符串到闭合的引号为止，其它 token 到分隔符为止
+    fn token_end(&mut self, eof: bool) -> Option<usize> {
+        let mut token = self.token.unwrap();
+        while self.pos < self.buf.len() {
+            let b = self.buf[self.pos];
+            if let Some(quote) = token.quote {
+                self.pos += 1;
+                if token.escape {
+                    token.escape = false;
+                } else if b == b'\\' {
+                    token.escape = true;
+                } else if b == quote {
+                    return Some(self.pos);
+                }
+            } else if b < 0x80 || !self.options.json5 {
+                if self.is_delimiter(b) {
+                    return Some(self.pos);
+                }
+                self.pos += 1;
+            } else {
+                match self.peek_char(false) {
+                    Ok(Some((_, true))) => return Some(self.pos),
+                    Ok(Some((width, false))) => self.pos += width,
+                    Ok(None) => break,
+                    // 非法的 UTF-8 也交给解析器，由它决定先报告哪个错误
+                    Err(_) => return Some(self.pos + 1),
+                }
+            }
+        }
+        self.token = Some(token);
+        if eof {
+            Some(self.buf.len())
+        } else {
+            None
+        }
+    }
+
+    // 解析到齐的 token，没有用完的字节留给后面的状态继续扫描
+    fn complete<H: Handler>(&mut self, end: usize, handler: &mut H) -> Result<(), &'static str> {
+        let start = self.token.take().unwrap().start;
+        let text = &self.buf[start..end];
+        if matches!(self.state, State::ObjectFirst | State::Key | State::ObjectComma) {
+            let (k, len) = if self.options.json5 {
+                parse_chars(text, &self.options, |p| p.parse_key())?
+            } else {
+                match ByteParser::scalar(text)? {
+                    (Json::STRING(k), len) => (k, len),
+                    _ => return Err("parse miss key"),
+                }
+            };
+            self.pos = start + len;
+            self.key = Some(k);
+            self.state = State::Colon;
+            return Ok(());
+        }
+        let (v, len) = if self.options.json5 {
+            parse_chars(text, &self.options, |p| p.parse_value())?
+        } else {
+            ByteParser::scalar(text)?
+        };
+        self.pos = start + len;
+        let ok = match v {
+            Json::NULL => handler.null(),
+            Json::TRUE => handler.bool(true),
+            Json::FALSE => handler.bool(false),
+            Json::NUMBER(n) => handler.number(n),
+            Json::STRING(s) => handler.string(s),
+            // 括号不会出现在 token 里
+            Json::ARRAY(_) | Json::OBJECT(_) => unreachable!(),
+        };
+        check(ok)?;
+        self.after_value();
+        Ok(())
+    }
+
+    // 输入结束时还在等待的东西就是 parse 对同样的文本报告的错误
+    fn end_of_input(&self) -> Result<(), &'static str> {
+        match self.comment {
+            Some(Comment::Slash) => return Err("parse invalid comment"),
+            Some(Comment::Block) | Some(Comment::BlockStar) => return Err("parse unterminated comment"),
+            _ => {}
+        }
+        match self.state {
+            State::End => Ok(()),
+            State::Value | State::ArrayFirst | State::ArrayComma => Err("expect value error"),
+            State::ArrayNext => Err("parse invalid array"),
+            State::ObjectFirst | State::Key | State::ObjectComma => Err("parse miss key"),
+            State::Colon => Err("parse miss colon"),
+            State::ObjectNext => Err("parse invalid object"),
+        }
+    }
+}
+
+// 注释结束时返回 None
+fn step_comment(c: Comment, b: u8) -> Result<Option<Comment>, &'static str> {
+    let next = match (c, b) {
+        (Comment::Slash, b'/') => Comment::Line,
+        (Comment::Slash, b'*') => Comment::Block,
+        (Comment::Slash, _) => return Err("parse invalid comment"),
+        (Comment::Line, b'\n') => return Ok(None),
+        (Comment::Line, _) => Comment::Line,
+        (Comment::Block, b'*') | (Comment::BlockStar, b'*') => Comment::BlockStar,
+        (Comment::BlockStar, b'/') => return Ok(None),
+        (Comment::Block, _) | (Comment::BlockStar, _) => Comment::Block,
+    };
+    Ok(Some(next))
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\n' | b'\r' | b'\t')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Event;
+    use crate::handler::TreeBuilder;
+    use crate::Parser;
+
+    // 按 size 切分后逐块喂进去，返回产生的事件和错误
+    fn feed_all(input: &[u8], size: usize, options: &ParseOptions) -> (Vec<Event>, Option<String>) {
+        let mut parser = IncrementalParser::with_options(options);
+        let mut events = vec![];
+        for chunk in input.chunks(size) {
+            if let Err(e) = parser.feed(chunk, &mut events) {
+                return (events, Some(e.to_string()));
+            }
+        }
+        let err = parser.finish(&mut events).err().map(|e| e.to_string());
+        (events, err)
+    }
+
+    // 每一种切分得到的事件和错误都要相同，构造出的值或者错误和 parse_with 相同
+    fn check_chunking(input: &[u8], options: &ParseOptions) -> (Vec<Event>, Option<String>) {
+        let expect = feed_all(input, input.len().max(1), options);
+        for size in 1..input.len() {
+            assert_eq!(feed_all(input, size, options), expect, "{:?} in chunks of {}", input, size);
+        }
+        let mut parser = IncrementalParser::with_options(options);
+        let mut tree = TreeBuilder::new();
+        let res = parser.feed(input, &mut tree).and_then(|_| parser.finish(&mut tree));
+        let got = res.map(|_| tree.into_json().unwrap()).map_err(|e| e.to_string());
+        assert_eq!(got, crate::parse_with(input, options).map_err(|e| e.to_string()), "{:?}", input);
+        expect
+    }
+
+    // 和一次性拉取的 Events 比较
+    fn pull_events(s: &str, options: &ParseOptions) -> (Vec<Event>, Option<String>) {
+        let mut events = vec![];
+        for e in Parser::with_options(s.chars(), options).events() {
+            match e {
+                Ok(e) => events.push(e),
+                Err(e) => return (events, Some(e.to_string())),
+            }
+        }
+        (events, None)
+    }
+
+    fn check(s: &str, options: &ParseOptions) -> (Vec<Event>, Option<String>) {
+        let got = check_chunking(s.as_bytes(), options);
+        assert_eq!(got, pull_events(s, options), "{:?}", s);
+        got
+    }
+
+    #[test]
+    fn test_any_chunking() {
+        let inputs = [
+            "{\"a\\\"]\": [1.5e-3, \"\u{4e2d}\\u00e9\", {\"b\": null}], \"c\": true}",
+            " -12.5e+3 ",
+            "\"\\uD834\\uDD1E\"",
+            "false",
+            "[[], {}, [0, -0.0]]",
+        ];
+        for input in inputs.iter() {
+            assert_eq!(check(input, &ParseOptions::default()).1, None);
+        }
+    }
+
+    #[test]
+    fn test_errors_match_parse() {
+        let inputs = [
+            "", "[1, 2", "[1,", "{\"a\" 1}", "{\"a\":", "{\"a\":1", "{", "{1:1}", "\"abc", "tru", "]",
+            "[1 2]", "[1-2]", "01", "1.", "1e309", "\"\\x\"", "[1,]", "{\"a\":1,}", "[\"\\u12\"]",
+        ];
+        for input in inputs.iter() {
+            let (_, err) = check(input, &ParseOptions::default());
+            assert_eq!(err.unwrap(), crate::parse(*input).unwrap_err().to_string(), "{:?}", input);
+        }
+        let got = check_chunking(b"[\"\xe4\xb8\"]", &ParseOptions::default());
+        assert_eq!(got, (vec![Event::StartArray], Some("parse invalid utf-8".to_string())));
+        let options = ParseOptions {
+            max_depth: 2,
+            ..ParseOptions::default()
+        };
+        assert_eq!(check("[[[]]]", &options).1.unwrap(), "parse nesting too deep");
+    }
+
+    #[test]
+    fn test_root_not_singular() {
+        let err = Some("parse root not singular".to_string());
+        assert_eq!(check("1 2", &ParseOptions::default()), (vec![Event::Number(1.0)], err.clone()));
+        let got = check("[1]x", &ParseOptions::default());
+        assert_eq!(got, (vec![Event::StartArray, Event::Number(1.0), Event::EndArray], err.clone()));
+        assert_eq!(check("{}{}", &ParseOptions::default()).1, err);
+        // 出错以后不能继续
+        let mut parser = IncrementalParser::new();
+        let mut events = vec![];
+        assert!(parser.feed(b"[1] ]", &mut events).is_err());
+        assert_eq!(parser.finish(&mut events).unwrap_err().to_string(), "parse root not singular");
+    }
+
+    #[test]
+    fn test_events_as_they_complete() {
+        let mut parser = IncrementalParser::new();
+        let mut events = vec![];
+        parser.feed(b"[1, {\"a\": tr", &mut events).unwrap();
+        assert_eq!(events, vec![Event::StartArray, Event::Number(1.0), Event::StartObject, Event::Key("a".to_string())]);
+        events.clear();
+        // 数字要等到分隔符才算完成
+        parser.feed(b"ue}, 2", &mut events).unwrap();
+        assert_eq!(events, vec![Event::Bool(true), Event::EndObject]);
+        events.clear();
+        parser.feed(b"5]", &mut events).unwrap();
+        assert_eq!(events, vec![Event::Number(25.0), Event::EndArray]);
+        parser.finish(&mut events).unwrap();
+    }
+
+    #[test]
+    fn test_options() {
+        let jsonc = ParseOptions {
+            jsonc: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(check("[1 /* ] */, 2] // ]\n/**/", &jsonc).1, None);
+        assert_eq!(check("[1/**/2]", &jsonc).1.unwrap(), "parse invalid array");
+        assert_eq!(check("1 /x", &jsonc).1.unwrap(), "parse invalid comment");
+        assert_eq!(check("1 /* x", &jsonc).1.unwrap(), "parse unterminated comment");
+        assert_eq!(check("1 /", &jsonc).1.unwrap(), "parse invalid comment");
+
+        let json5 = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        let input = "{a: 1, 'b': ']', c$: [+Infinity,\u{2003}.5, 0x1F,], d: 'x\\'}',} \u{a0}// end";
+        assert_eq!(check(input, &json5).1, None);
+        assert_eq!(check("{a b: 1}", &json5).1.unwrap(), "parse miss colon");
+        assert_eq!(check("{\\u0061\\u0062: 1}", &json5).0[1], Event::Key("ab".to_string()));
+        assert_eq!(check("{a\\u002d: 1}", &json5).1.unwrap(), "parse invalid identifier escape");
+        assert_eq!(check("[1é]", &json5).1.unwrap(), "parse invalid array");
+        assert_eq!(check("{a: 1}", &ParseOptions::default()).1.unwrap(), "parse miss key");
+        for input in [&b"[1, tru\xff]"[..], b"[1 \xe4\xb8", b"// \xff\n1", b"[1x\xff]"].iter() {
+            let expect = crate::parse_with(*input, &json5).unwrap_err().to_string();
+            assert_eq!(check_chunking(input, &json5).1.unwrap(), expect, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_encodings() {
+        let expect = (vec![Event::StartArray, Event::Number(1.0), Event::EndArray], None);
+        assert_eq!(check_chunking(b"\xef\xbb\xbf[1]", &ParseOptions::default()), expect);
+        let utf16: Vec<u8> = "[1]".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        assert_eq!(check_chunking(&utf16, &ParseOptions::default()), expect);
+        let utf8_only = ParseOptions {
+            utf8_only: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(check_chunking(&utf16, &utf8_only).1.unwrap(), "parse input not utf-8");
+        assert_eq!(check_chunking(b"1", &ParseOptions::default()), (vec![Event::Number(1.0)], None));
+    }
+}
@@ -0,0 +1,252 @@
+// 统一的输入来源：字符串和字节走 ByteParser，char 迭代器走 Parser，reader 边读边解码
+// 不管输入来自哪里，使用的选项和返回的错误都是一样的
+// 字节输入会先检测编码，去掉 BOM，必要时转码成 UTF-8
+// 记录位置时都先变成字节再交给 ByteParser，位置是 UTF-8 字节的偏移
+
+use std::io::Read;
+use std::str::{self, Chars};
+
+use crate::encoding::{self, Encoding};
+use crate::spans::SpanMap;
+use crate::{read, ByteParser, Json, ParseError, ParseOptions, Parser};
+
+// ByteParser 只认识标准 JSON 和 JSONC，JSON5 改用按字符解析的 Parser
+pub(crate) fn parse_utf8(bytes: &[u8], options: &ParseOptions) -> Result<Json, &'static str> {
+    if options.json5 {
+        parse_chars(bytes, options, |p| p.parse_document()).map(|(v, _)| v)
+    } else {
+        ByteParser::with_options(bytes, options).parse()
+    }
+}
+
+// 用按字符解析的 Parser 解析 bytes 开头的内容，f 决定解析的是整个文本、一个值还是一个 key，
+// 返回结果和用掉的字节数
+// 非法的 UTF-8 和从 reader 解析时一样处理：解析器真的读到了非法的字节才报告 parse invalid utf-8，
+// 在那之前出现的语法错误照常返回
+pub(crate) fn parse_chars<R, F>(bytes: &[u8], options: &ParseOptions, f: F) -> Result<(R, usize), &'static str>
+where
+    F: FnOnce(&mut Parser<Chars>) -> Result<R, &'static str>,
+{
+    let (s, valid) = match str::from_utf8(bytes) {
+        Ok(s) => (s, true),
+        Err(e) => (str::from_utf8(&bytes[..e.valid_up_to()]).unwrap(), false),
+    };
+    let mut parser = Parser::with_options(s.chars(), options);
+    let res = f(&mut parser);
+    // 解析器读完了合法的部分，说明它还需要后面非法的字节
+    if !valid && parser.ch.is_none() {
+        return Err("parse invalid utf-8");
+    }
+    let rest = parser.ch.map_or(0, char::len_utf8) + parser.rest_chars.as_str().len();
+    Ok((res?, s.len() - rest))
+}
+
+/// A source that a complete JSON text can be parsed from.
+pub trait Input {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError>;
+
+    /// Like `parse_with`, and also records where every value and key appears in the input.
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError>;
+}
+
+/// Wraps any `Iterator<Item = char>` so it can be passed to `parse`.
+pub struct FromChars<I>(pub I);
+
+/// Wraps any `io::Read` so it can be passed to `parse`.
+pub struct FromReader<R>(pub R);
+
+impl Input for &[u8] {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        let bytes = encoding::to_utf8(self, options)?;
+        Ok(parse_utf8(&bytes, options)?)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        let bytes = encoding::to_utf8(self, options)?;
+        let (v, mut spans) = ByteParser::with_options(&bytes, options).parse_with_spans()?;
+        // UTF-8 输入的位置指向原来的字节，跳过的 BOM 也要算上；转码过的输入只能指向转码后的文本
+        if let (Encoding::Utf8, bom) = encoding::detect(self) {
+            spans.shift(bom);
+        }
+        Ok((v, spans))
+    }
+}
+
+impl<const N: usize> Input for &[u8; N] {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        self[..].parse_with(options)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        self[..].parse_with_spans(options)
+    }
+}
+
+impl Input for &Vec<u8> {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        self.as_slice().parse_with(options)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        self.as_slice().parse_with_spans(options)
+    }
+}
+
+impl Input for Vec<u8> {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        self.as_slice().parse_with(options)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        self.as_slice().parse_with_spans(options)
+    }
+}
+
+impl Input for &str {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        self.as_bytes().parse_with(options)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        self.as_bytes().parse_with_spans(options)
+    }
+}
+
+impl Input for &String {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        self.as_bytes().parse_with(options)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        self.as_bytes().parse_with_spans(options)
+    }
+}
+
+impl Input for String {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        self.as_bytes().parse_with(options)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        self.as_bytes().parse_with_spans(options)
+    }
+}
+
+impl<'a> Input for std::str::Chars<'a> {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        FromChars(self).parse_with(options)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        FromChars(self).parse_with_spans(options)
+    }
+}
+
+impl<I: Iterator<Item = char>> Input for FromChars<I> {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        let mut chars = self.0.peekable();
+        if chars.peek() == Some(&'\u{feff}') {
+            chars.next();
+        }
+        Ok(Parser::with_options(chars, options).parse_document()?)
+    }
+
+    fn parse_with_spans(self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        self.0.collect::<String>().parse_with_spans(options)
+    }
+}
+
+impl<R: Read> Input for FromReader<R> {
+    fn parse_with(self, options: &ParseOptions) -> Result<Json, ParseError> {
+        read::parse_reader(self.0, options)
+    }
+
+    fn parse_with_spans(mut self, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+        let mut bytes = vec![];
+        self.0.read_to_end(&mut bytes)?;
+        bytes.parse_with_spans(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 同一段文本从每一种输入来源解析，结果和错误都要一样
+    fn parse_all(s: &str, options: &ParseOptions) -> Vec<Result<Json, String>> {
+        let bytes = s.as_bytes().to_vec();
+        let results = vec![
+            s.parse_with(options),
+            (&s.to_string()).parse_with(options),
+            s.to_string().parse_with(options),
+            s.as_bytes().parse_with(options),
+            (&bytes).parse_with(options),
+            bytes.clone().parse_with(options),
+            s.chars().parse_with(options),
+            FromChars(s.chars()).parse_with(options),
+            FromReader(s.as_bytes()).parse_with(options),
+        ];
+        results.into_iter().map(|r| r.map_err(|e| e.to_string())).collect()
+    }
+
+    #[test]
+    fn test_every_source() {
+        let options = ParseOptions {
+            max_depth: 3,
+            ..ParseOptions::default()
+        };
+        let expect = Json::from(vec![Json::from("é😀"), Json::from(vec![1.5, -2.0])]);
+        for s in &["[\"\\u00e9😀\", [1.5, -2e0]]", "\u{feff} [\"é\\ud83d\\ude00\", [1.5, -2]] "] {
+            for res in parse_all(s, &options) {
+                assert_eq!(res, Ok(expect.clone()));
+            }
+        }
+        for (s, e) in &[
+            ("[[[[]]]]", "parse nesting too deep"),
+            ("[1, 2", "parse invalid array"),
+            ("{\"a\" 1}", "parse miss colon"),
+            ("[1] x", "parse root not singular"),
+            ("", "expect value error"),
+        ] {
+            for res in parse_all(s, &options) {
+                assert_eq!(res, Err(e.to_string()), "{:?}", s);
+            }
+        }
+        let jsonc = ParseOptions {
+            jsonc: true,
+            ..ParseOptions::default()
+        };
+        for res in parse_all("[true, /* x */ null] // end", &jsonc) {
+            assert_eq!(res, Ok(Json::from(vec![Some(true), None])));
+        }
+        // 固定长度的数组也可以直接解析
+        assert_eq!(b"[true]".parse_with(&options).unwrap(), Json::from(vec![true]));
+    }
+
+    #[test]
+    fn test_spans_every_source() {
+        let options = ParseOptions::default();
+        let s = "{\"a\": [1, \"é\"]}";
+        let bytes = s.as_bytes().to_vec();
+        let (json, spans) = crate::parse_with_spans(s, &options).unwrap();
+        assert_eq!(json, crate::parse(s).unwrap());
+        for res in [
+            (&s.to_string()).parse_with_spans(&options),
+            s.to_string().parse_with_spans(&options),
+            s.as_bytes().parse_with_spans(&options),
+            (&bytes).parse_with_spans(&options),
+            bytes.clone().parse_with_spans(&options),
+            s.chars().parse_with_spans(&options),
+            FromChars(s.chars()).parse_with_spans(&options),
+            FromReader(s.as_bytes()).parse_with_spans(&options),
+        ] {
+            assert_eq!(res.unwrap(), (json.clone(), spans.clone()));
+        }
+        // 有 BOM 时位置仍然是原来输入里的字节偏移
+        let s = "\u{feff}[1, 22]";
+        let (_, spans) = crate::parse_with_spans(s, &options).unwrap();
+        let span = spans.value("$[1]").unwrap();
+        assert_eq!(&s[span.start..span.end], "22");
+        assert_eq!(crate::parse_with_spans("[1] x", &options).unwrap_err().to_string(), "parse root not singular");
+    }
+}
@@ -0,0 +1,173 @@
+// RFC 7464 JSON 文本序列（application/json-seq）
+// 每条记录以 RS (0x1E) 开头、以 LF 结尾，RS 之间的字节就是一条记录
+//
+// 写入者中途崩溃时最后一条记录可能被截断，RFC 要求解析器：
+// - 解析失败的记录报告错误后继续读下一条，下一个 RS 就是新的起点
+// - 顶层是数字或 true/false/null 时，后面必须跟着空白，否则可能被截断了，例如 123 被截成 12
+// - 连续的 RS 不代表空记录，直接忽略
+// 第一个 RS 之前只能有空白，否则单独报一个错误，不算作记录；记录的错误用 Record 标出是第几条
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::{input, Json, ParseError, ParseOptions};
+
+const RS: u8 = 0x1E;
+
+/// Reads the records of a JSON text sequence.
+///
+/// A truncated or malformed record is reported as `ParseError::Record` and reading resumes at the next RS.
+/// Anything but whitespace before the first RS is reported as "parse data before record separator".
+pub struct JsonSeqReader<R: Read> {
+    reader: BufReader<R>,
+    options: ParseOptions,
+    buf: Vec<u8>,
+    record: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<R: Read> JsonSeqReader<R> {
+    pub fn new(reader: R) -> Self {
+        JsonSeqReader::with_options(reader, &ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: &ParseOptions) -> Self {
+        JsonSeqReader {
+            reader: BufReader::new(reader),
+            options: options.clone(),
+            buf: vec![],
+            record: 0,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Index (starting at 1) of the most recently returned record.
+    pub fn record(&self) -> usize {
+        self.record
+    }
+
+    fn parse_record(&self, text: &[u8]) -> Result<Json, ParseError> {
+        // 容器和字符串被截断一定会解析失败，只有数字和字面量需要看后面有没有空白
+        let first = text.iter().find(|b| !is_whitespace(**b));
+        let scalar = !matches!(first, Some(b'[') | Some(b'{') | Some(b'"'));
+        if scalar && !text.last().is_some_and(|b| is_whitespace(*b)) {
+            return Err(ParseError::Syntax("parse truncated record"));
+        }
+        Ok(input::parse_utf8(text, &self.options)?)
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\n' | b'\r' | b'\t')
+}
+
+impl<R: Read> Iterator for JsonSeqReader<R> {
+    type Item = Result<Json, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buf.clear();
+            match self.reader.read_until(RS, &mut self.buf) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    if self.buf.last() == Some(&RS) {
+                        self.buf.pop();
+                    }
+                    if !self.started {
+                        self.started = true;
+                        if self.buf.iter().all(|b| is_whitespace(*b)) {
+                            continue;
+                        }
+                        return Some(Err(ParseError::Syntax("parse data before record separator")));
+                    }
+                    // 连续的 RS 之间没有内容
+                    if self.buf.is_empty() {
+                        continue;
+                    }
+                    self.record += 1;
+                    let res = self.parse_record(&self.buf);
+                    return Some(res.map_err(|e| ParseError::Record(self.record, Box::new(e))));
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(ParseError::Io(e)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Writes each value as an RS-prefixed, LF-terminated record.
+pub struct JsonSeqWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonSeqWriter<W> {
+    pub fn new(out: W) -> Self {
+        JsonSeqWriter { out }
+    }
+
+    pub fn write(&mut self, json: &Json) -> io::Result<()> {
+        writeln!(self.out, "\x1e{}", json)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let values = vec![crate::parse("{\"a\": [1, 2]}").unwrap(), Json::NUMBER(3.0), Json::NULL];
+        let mut writer = JsonSeqWriter::new(vec![]);
+        for v in values.iter() {
+            writer.write(v).unwrap();
+        }
+        let out = writer.into_inner();
+        assert_eq!(out, b"\x1e{\"a\":[1,2]}\n\x1e3\n\x1enull\n");
+        let back: Vec<Json> = JsonSeqReader::new(&out[..]).map(Result::unwrap).collect();
+        assert_eq!(back, values);
+    }
+
+    #[test]
+    fn test_truncated_records() {
+        let input = b"\x1e{\"a\": 1\x1e\x1e12\x1etrue\n\x1e\"x\"";
+        let got: Vec<_> = JsonSeqReader::new(&input[..]).collect();
+        assert_eq!(got.len(), 4);
+        assert_eq!(got[0].as_ref().unwrap_err().to_string(), "record 1: parse invalid object");
+        assert_eq!(got[1].as_ref().unwrap_err().to_string(), "record 2: parse truncated record");
+        assert_eq!(got[2].as_ref().unwrap(), &Json::TRUE);
+        assert_eq!(got[3].as_ref().unwrap(), &Json::STRING("x".to_string()));
+    }
+
+    #[test]
+    fn test_before_first_record() {
+        let mut reader = JsonSeqReader::new(&b"12
+[1
+2
+"[..]);
+        assert!(matches!(reader.next(), Some(Err(ParseError::Syntax("parse data before record separator")))));
+        assert!(matches!(reader.next(), Some(Err(ParseError::Record(1, _)))));
+        assert_eq!(reader.next().unwrap().unwrap(), Json::NUMBER(2.0));
+        assert_eq!(reader.record(), 2);
+        assert!(reader.next().is_none());
+        // 开头只有空白时直接忽略
+        let got: Vec<Json> = JsonSeqReader::new(&b" 
+null
+"[..]).map(Result::unwrap).collect();
+        assert_eq!(got, vec![Json::NULL]);
+        assert!(matches!(JsonSeqReader::new(&b"1
+"[..]).next(), Some(Err(ParseError::Syntax(_)))));
+    }
+}
@@ -0,0 +1,219 @@
+// 独立的词法分析器：把输入切成带位置的 token，包括空白和注释，拼起来正好是原来的输入
+// 字符串、数字和字面量直接用 ByteParser 的代码切分和检查，边界和错误都和 parse 完全一致
+// 词法分析不检查 token 的顺序，比如 [ ] ] 也能切分，语法错误留给解析器
+// 注释只在打开 jsonc 选项时识别，JSON5 不支持
+
+use crate::spans::Span;
+use crate::{ByteParser, Json, ParseError, ParseOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// One of `{ } [ ] : ,`.
+    Punctuation,
+    String,
+    Number,
+    /// `true`, `false` or `null`.
+    Literal,
+    Whitespace,
+    Comment,
+}
+
+/// A token and the exact text it was lexed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// Splits a JSON text into tokens, including whitespace and comments.
+///
+/// Iteration stops after the first error.
+pub struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+    comments: bool,
+    json5: bool,
+    done: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Lexer::with_options(src, &ParseOptions::default())
+    }
+
+    pub fn with_options(src: &'a str, options: &ParseOptions) -> Self {
+        Lexer {
+            src,
+            pos: 0,
+            line: 1,
+            column: 1,
+            comments: options.jsonc,
+            json5: options.json5,
+            done: false,
+        }
+    }
+
+    fn byte(&self, i: usize) -> Option<u8> {
+        self.src.as_bytes().get(i).copied()
+    }
+
+    // 从 pos 开始满足 f 的字节的结束位置
+    fn scan_while(&self, f: impl Fn(u8) -> bool) -> usize {
+        let bytes = self.src.as_bytes();
+        let mut end = self.pos;
+        while end < bytes.len() && f(bytes[end]) {
+            end += 1;
+        }
+        end
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, &'static str> {
+        if self.json5 {
+            return Err("parse json5 not supported");
+        }
+        let (kind, end) = match self.byte(self.pos) {
+            None => return Ok(None),
+            // 开头的 BOM 当作空白，拼接 token 仍然得到原文
+            Some(0xef) if self.pos == 0 && self.src.starts_with('\u{feff}') => (TokenKind::Whitespace, 3),
+            Some(b'{') | Some(b'}') | Some(b'[') | Some(b']') | Some(b':') | Some(b',') => {
+                (TokenKind::Punctuation, self.pos + 1)
+            }
+            Some(b' ') | Some(b'\n') | Some(b'\r') | Some(b'\t') => {
+                (TokenKind::Whitespace, self.scan_while(|b| matches!(b, b' ' | b'\n' | b'\r' | b'\t')))
+            }
+            Some(b'/') if self.comments => (TokenKind::Comment, comment_end(self.src.as_bytes(), self.pos)?),
+            Some(_) => {
+                let (v, len) = ByteParser::scalar(&self.src.as_bytes()[self.pos..])?;
+                let kind = match v {
+                    Json::STRING(_) => TokenKind::String,
+                    Json::NUMBER(_) => TokenKind::Number,
+                    _ => TokenKind::Literal,
+                };
+                (kind, self.pos + len)
+            }
+        };
+        let text = &self.src[self.pos..end];
+        let span = Span {
+            start: self.pos,
+            end,
+            line: self.line,
+            column: self.column,
+        };
+        for c in text.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if c != '\u{feff}' {
+                self.column += 1;
+            }
+        }
+        self.pos = end;
+        Ok(Some(Token { kind, text, span }))
+    }
+}
+
+/// Returns the end of the comment starting at `bytes[pos]`, which must be `/`.
+///
+/// A line comment ends before its newline.
+pub(crate) fn comment_end(bytes: &[u8], pos: usize) -> Result<usize, &'static str> {
+    match bytes.get(pos + 1) {
+        Some(b'/') => Ok(bytes[pos..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |i| pos + i)),
+        Some(b'*') => match bytes[pos + 2..].windows(2).position(|w| w == b"*/") {
+            Some(i) => Ok(pos + i + 4),
+            None => Err("parse unterminated comment"),
+        },
+        _ => Err("parse invalid comment"),
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_token() {
+            Ok(Some(token)) => Some(Ok(token)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(ParseError::Syntax(e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens<'a>(s: &'a str, options: &ParseOptions) -> Vec<Token<'a>> {
+        Lexer::with_options(s, options).map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        let input = "{\"a\\n\": [1.5e3, true],\n \"\u{4e2d}\": null}";
+        let got = tokens(input, &ParseOptions::default());
+        assert_eq!(got.iter().map(|t| t.text).collect::<String>(), input);
+        let kinds: Vec<TokenKind> = got.iter().map(|t| t.kind).collect();
+        use TokenKind::{Literal as L, Number as N, Punctuation as P, String as S, Whitespace as W};
+        assert_eq!(kinds, vec![P, S, P, W, P, N, P, W, L, P, P, W, S, P, W, L, P]);
+        let null = got[15];
+        assert_eq!((null.text, null.span.line, null.span.column), ("null", 2, 7));
+        assert_eq!(&input[null.span.start..null.span.end], "null");
+
+        // BOM 不算一列，和 parse_with_spans 的列号一致
+        let input = "\u{feff}[1]";
+        let got = tokens(input, &ParseOptions::default());
+        assert_eq!(got.iter().map(|t| t.text).collect::<String>(), input);
+        assert_eq!((got[0].kind, got[0].text), (W, "\u{feff}"));
+        assert_eq!((got[1].text, got[1].span.start, got[1].span.column), ("[", 3, 1));
+        let (_, spans) = crate::parse_with_spans(input, &ParseOptions::default()).unwrap();
+        let root = spans.value("$").unwrap();
+        assert_eq!((root.start, root.line, root.column), (3, 1, 1));
+    }
+
+    #[test]
+    fn test_minify() {
+        let options = ParseOptions {
+            jsonc: true,
+            ..ParseOptions::default()
+        };
+        let input = "{\n  // comment\n  \"a\": [ 1, 2 ] /* x */\n}";
+        let minified: String = tokens(input, &options)
+            .iter()
+            .filter(|t| !matches!(t.kind, TokenKind::Whitespace | TokenKind::Comment))
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(minified, "{\"a\":[1,2]}");
+    }
+
+    #[test]
+    fn test_lexer_errors() {
+        let err = |s: &str| Lexer::new(s).find_map(Result::err).unwrap().to_string();
+        assert_eq!(err("[1.]"), "parse invalid number");
+        assert_eq!(err("[nul]"), "parse invalid literal");
+        assert_eq!(err("\"a\\x\""), "parse invalid string escape");
+        assert_eq!(err("\"abc"), "parse miss quotation mark");
+        assert_eq!(err("[1] // x"), "parse value error");
+        assert_eq!(err("1e999"), "parse number too big");
+        // 词法分析和 parse 在同样的位置切分 token，顺序错误留给解析器
+        let texts: Vec<&str> = tokens("[1-2]", &ParseOptions::default()).iter().map(|t| t.text).collect();
+        assert_eq!(texts, vec!["[", "1", "-2", "]"]);
+        assert_eq!(crate::parse("[1-2]").unwrap_err().to_string(), "parse invalid array");
+        assert_eq!(crate::cst::parse_cst("[1-2]").unwrap_err().to_string(), "parse invalid array");
+        let json5 = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(Lexer::with_options("1", &json5).next().unwrap().unwrap_err().to_string(), "parse json5 not supported");
+    }
+}
@@ -0,0 +1,864 @@
+// 语法以按字符解析的 Parser（本文件）为准，它是唯一实现了全部语法（JSON、JSONC、JSON5）的解析器
+// 其余解析器都要和它给出同样的值和同样的错误：
+// - ByteParser（bytes.rs）是 JSON 和 JSONC 的快速实现，parse_with 在不需要 JSON5 时用它
+// - IncrementalParser、parse_partial、Lexer 和 CST 自己只切分 token，
+//   字符串、数字和字面量交给 ByteParser::scalar；前两个的 JSON5 token 交给 Parser，后两个不支持 JSON5
+// 改动语法时先改 Parser，再让其它实现跟上
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+
+// 让 derive 生成的 ::json_parser 路径在本 crate 里也能用
+extern crate self as json_parser;
+
+#[macro_use]
+mod macros;
+
+mod access;
+pub mod borrowed;
+pub mod bytes;
+pub mod color;
+mod convert;
+pub mod cst;
+pub mod encoding;
+pub mod events;
+pub mod handler;
+pub mod html;
+pub mod incremental;
+pub mod input;
+pub mod json_seq;
+pub mod lexer;
+pub mod ndjson;
+pub mod partial;
+pub mod read;
+pub mod serialize;
+pub mod spans;
+
+pub use bytes::{parse_bytes, parse_stream, ByteParser, ValueStream};
+pub use convert::TypeError;
+pub use cst::{parse_cst, Cst};
+pub use events::{Event, Events};
+pub use handler::Handler;
+pub use incremental::IncrementalParser;
+pub use ndjson::{NdjsonReader, NdjsonWriter};
+pub use partial::{parse_partial, parse_partial_with, Partial};
+pub use input::{FromChars, FromReader, Input};
+pub use json_seq::{JsonSeqReader, JsonSeqWriter};
+pub use lexer::{Lexer, Token, TokenKind};
+pub use read::from_reader;
+pub use json_parser_derive::{FromJson, ToJson};
+pub use serialize::{FromJson, FromJsonError, ToJson};
+pub use spans::{Span, SpanMap};
+
+#[allow(clippy::upper_case_acronyms, clippy::vec_box)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    NULL,
+    TRUE,
+    FALSE,
+    /// Every JSON number, stored as `f64`; this used to be `u32`.
+    NUMBER(f64),
+    STRING(String),
+    ARRAY(Vec<Box<Json>>),
+    OBJECT(HashMap<String, Box<Json>>),
+}
+
+/// Options for `parse_with` and every parser, reader and lexer with a `with_options` constructor.
+///
+/// `json5` is implemented by the char-based `Parser`, which `parse_with`, the readers and
+/// `IncrementalParser` switch to when it is set. `ByteParser` and `Lexer` work on byte offsets
+/// and reject it with "parse json5 not supported". Only the input sources and readers transcode
+/// UTF-16 and UTF-32; `ByteParser` always expects UTF-8.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Maximum nesting depth of arrays and objects.
+    pub max_depth: usize,
+    /// Reject UTF-16 and UTF-32 input instead of transcoding it (RFC 8259 section 8.1).
+    pub utf8_only: bool,
+    /// Accept JSON5: comments, unquoted keys, single quotes, trailing commas and more.
+    pub json5: bool,
+    /// Accept `//` and `/* */` comments wherever whitespace is allowed, keeping the rest strict (JSONC).
+    pub jsonc: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_depth: 128,
+            utf8_only: false,
+            json5: false,
+            jsonc: false,
+        }
+    }
+}
+
+pub struct Parser<T: Iterator<Item = char>> {
+    ch: Option<char>,
+    rest_chars: T,
+    depth: usize,
+    max_depth: usize,
+    json5: bool,
+    comments: bool,
+    // stack: Vec<Box<Json>>,
+}
+
+impl<T> Parser<T>
+    where T: Iterator<Item = char>
+{
+    pub fn new(input: T) -> Self {
+        Parser::with_options(input, &ParseOptions::default())
+    }
+
+    pub fn with_options(input: T, options: &ParseOptions) -> Self {
+        let mut parser = Parser {
+            ch: None,
+            rest_chars: input,
+            depth: 0,
+            max_depth: options.max_depth,
+            json5: options.json5,
+            comments: options.json5 || options.jsonc,
+        };
+        parser.next_char();
+        parser
+    }
+
+    fn next_char(&mut self) {
+        let ch = self.rest_chars.next();
+        match ch {
+            Some(x) => self.ch = Some(x),
+            None => self.ch = None,
+        }
+    }
+
+    fn parse_whitespace(&mut self) -> Result<(), &'static str>
+    {
+        loop {
+            match self.ch {
+                Some(' ') | Some('\n') | Some('\r') | Some('\t') => self.next_char(),
+                // JSON5 的空白还包括 Unicode 的空格类字符和 BOM
+                Some(c) if self.json5 && (c.is_whitespace() || c == '\u{feff}') => self.next_char(),
+                Some('/') if self.comments => self.parse_comment()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    // 行注释到换行为止，换行本身留给 parse_whitespace 跳过
+    fn parse_comment(&mut self) -> Result<(), &'static str>
+    {
+        self.next_char();
+        match self.ch {
+            Some('/') => {
+                while !matches!(self.ch, Some('\n') | None) {
+                    self.next_char();
+                }
+                Ok(())
+            }
+            Some('*') => {
+                self.next_char();
+                loop {
+                    match self.ch {
+                        Some('*') => {
+                            self.next_char();
+                            if self.ch == Some('/') {
+                                self.next_char();
+                                return Ok(());
+                            }
+                        }
+                        Some(_) => self.next_char(),
+                        None => return Err("parse unterminated comment"),
+                    }
+                }
+            }
+            _ => Err("parse invalid comment"),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, &'static str>
+    {
+        match self.ch {
+            Some('n') => self.parse_literals("null"),
+            Some('t') => self.parse_literals("true"),
+            Some('f') => self.parse_literals("false"),
+            Some('"') => self.parse_string().map(Json::STRING),
+            Some('[') => self.parse_nested(Self::parse_array),
+            Some('{') => self.parse_nested(Self::parse_object),
+            Some('-') => self.parse_number(),
+            Some(n) if n.is_ascii_digit() => self.parse_number(),
+            Some('\'') if self.json5 => self.parse_string().map(Json::STRING),
+            Some('+') | Some('.') | Some('I') | Some('N') if self.json5 => self.parse_number(),
+            Some(_) => Err("parse value error"),
+            None => Err("expect value error"),
+        }
+    }
+
+    // 限制数组和对象的嵌套层数，避免恶意输入把递归的解析栈撑爆
+    fn parse_nested<R, F>(&mut self, f: F) -> Result<R, &'static str>
+        where F: FnOnce(&mut Self) -> Result<R, &'static str>
+    {
+        if self.depth >= self.max_depth {
+            return Err("parse nesting too deep");
+        }
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        res
+    }
+
+    fn parse_literals(&mut self, s: &str) -> Result<Json, &'static str>
+    {
+        for x in s.chars() {
+            match self.ch {
+                Some(y) => {
+                    if x != y {
+                        return Err("parse invalid literal");
+                    }
+                    self.next_char();
+                }
+                None => return Err("parse invalid literal"),
+            }
+        }
+        match s {
+            "null" => Ok(Json::NULL),
+            "true" => Ok(Json::TRUE),
+            "false" => Ok(Json::FALSE),
+            "Infinity" => Ok(Json::NUMBER(f64::INFINITY)),
+            "NaN" => Ok(Json::NUMBER(f64::NAN)),
+            _ => Err("parse unknown literal"),
+        }
+    }
+
+    fn push_digits(&mut self, buf: &mut String) -> usize
+    {
+        let mut count = 0;
+        while let Some(ch) = self.ch {
+            if !ch.is_ascii_digit() {
+                break;
+            }
+            buf.push(ch);
+            count += 1;
+            self.next_char();
+        }
+        count
+    }
+
+    // number = [ minus ] int [ frac ] [ exp ]
+    // JSON5 还允许 + 号、十六进制、Infinity、NaN，以及小数点前面或后面没有数字
+    fn parse_number(&mut self) -> Result<Json, &'static str>
+    {
+        let mut buf = String::new();
+        let mut negative = false;
+        match self.ch {
+            Some('-') => {
+                negative = true;
+                buf.push('-');
+                self.next_char();
+            }
+            Some('+') if self.json5 => self.next_char(),
+            _ => {}
+        }
+        let sign = if negative { -1.0 } else { 1.0 };
+        let mut int_digits = 0;
+        match self.ch {
+            Some('0') => {
+                buf.push('0');
+                int_digits = 1;
+                self.next_char();
+                if self.json5 && matches!(self.ch, Some('x') | Some('X')) {
+                    self.next_char();
+                    return self.parse_hex_number().map(|n| Json::NUMBER(sign * n));
+                }
+            }
+            Some('1'..='9') => {
+                int_digits = self.push_digits(&mut buf);
+            }
+            Some('.') if self.json5 => {}
+            Some('I') if self.json5 => {
+                self.parse_literals("Infinity")?;
+                return Ok(Json::NUMBER(sign * f64::INFINITY));
+            }
+            Some('N') if self.json5 => {
+                self.parse_literals("NaN")?;
+                return Ok(Json::NUMBER(f64::NAN));
+            }
+            _ => return Err("parse invalid number"),
+        }
+        if self.ch == Some('.') {
+            buf.push('.');
+            self.next_char();
+            let frac_digits = self.push_digits(&mut buf);
+            if frac_digits == 0 && !(self.json5 && int_digits > 0) {
+                return Err("parse invalid number");
+            }
+        }
+        if let Some(e @ 'e') | Some(e @ 'E') = self.ch {
+            buf.push(e);
+            self.next_char();
+            if let Some(sign @ '+') | Some(sign @ '-') = self.ch {
+                buf.push(sign);
+                self.next_char();
+            }
+            if self.push_digits(&mut buf) == 0 {
+                return Err("parse invalid number");
+            }
+        }
+        let n: f64 = buf.parse().map_err(|_| "parse invalid number")?;
+        if n.is_infinite() {
+            return Err("parse number too big");
+        }
+        Ok(Json::NUMBER(n))
+    }
+
+    fn parse_hex_number(&mut self) -> Result<f64, &'static str>
+    {
+        let mut n = 0.0;
+        let mut count = 0;
+        while let Some(d) = self.ch.and_then(|c| c.to_digit(16)) {
+            n = n * 16.0 + f64::from(d);
+            count += 1;
+            self.next_char();
+        }
+        if count == 0 {
+            return Err("parse invalid number");
+        }
+        Ok(n)
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, &'static str>
+    {
+        let mut u = 0;
+        for _ in 0..4 {
+            let d = self.ch.and_then(|c| c.to_digit(16)).ok_or("parse invalid unicode hex")?;
+            u = u << 4 | d;
+            self.next_char();
+        }
+        Ok(u)
+    }
+
+    fn parse_escape(&mut self, s: &mut String) -> Result<(), &'static str>
+    {
+        let ch = match self.ch {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                self.next_char();
+                let mut u = self.parse_hex4()?;
+                if (0xDC00..=0xDFFF).contains(&u) {
+                    return Err("parse invalid unicode surrogate");
+                }
+                if (0xD800..=0xDBFF).contains(&u) {
+                    if self.ch != Some('\\') {
+                        return Err("parse invalid unicode surrogate");
+                    }
+                    self.next_char();
+                    if self.ch != Some('u') {
+                        return Err("parse invalid unicode surrogate");
+                    }
+                    self.next_char();
+                    let low = self.parse_hex4()?;
+                    if !(0xDC00..=0xDFFF).contains(&low) {
+                        return Err("parse invalid unicode surrogate");
+                    }
+                    u = 0x10000 + ((u - 0xD800) << 10) + (low - 0xDC00);
+                }
+                s.push(char::from_u32(u).ok_or("parse invalid unicode surrogate")?);
+                return Ok(());
+            }
+            Some(c) if self.json5 => return self.parse_json5_escape(c, s),
+            Some(_) => return Err("parse invalid string escape"),
+            None => return Err("parse miss quotation mark"),
+        };
+        self.next_char();
+        s.push(ch);
+        Ok(())
+    }
+
+    fn parse_json5_escape(&mut self, c: char, s: &mut String) -> Result<(), &'static str>
+    {
+        self.next_char();
+        match c {
+            'v' => s.push('\u{b}'),
+            '0' if !matches!(self.ch, Some('0'..='9')) => s.push('\0'),
+            'x' => {
+                let mut u = 0;
+                for _ in 0..2 {
+                    let d = self.ch.and_then(|c| c.to_digit(16)).ok_or("parse invalid string escape")?;
+                    u = u << 4 | d;
+                    self.next_char();
+                }
+                // 两位十六进制数最大是 0xFF，一定是合法的 char
+                s.push(char::from_u32(u).unwrap());
+            }
+            // 反斜杠后面紧跟换行表示字符串在下一行继续
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            '\r' => {
+                if self.ch == Some('\n') {
+                    self.next_char();
+                }
+            }
+            '0'..='9' => return Err("parse invalid string escape"),
+            c => s.push(c),
+        }
+        Ok(())
+    }
+
+    // 开头的引号可以是 " 或者（JSON5 中）'，字符串以同样的引号结束
+    fn parse_string(&mut self) -> Result<String, &'static str>
+    {
+        let quote = self.ch;
+        let mut s = String::new();
+        self.next_char();
+        loop {
+            match self.ch {
+                Some('\\') => {
+                    self.next_char();
+                    self.parse_escape(&mut s)?;
+                }
+                Some(x) if Some(x) == quote => {
+                    self.next_char();
+                    return Ok(s);
+                }
+                Some('\n') | Some('\r') => return Err("parse invalid string char"),
+                Some(x) if (x as u32) < 0x20 && !self.json5 => return Err("parse invalid string char"),
+                Some(x) => {
+                    s.push(x);
+                    self.next_char();
+                }
+                None => return Err("parse miss quotation mark"),
+            }
+        }
+    }
+
+    // 标识符里可以用 \uXXXX 写任意一个标识符字符，比如 a\u0062 就是 ab
+    fn parse_identifier(&mut self) -> Result<String, &'static str>
+    {
+        let mut s = String::new();
+        loop {
+            match self.ch {
+                Some('\\') => {
+                    self.next_char();
+                    if self.ch != Some('u') {
+                        return Err("parse invalid identifier escape");
+                    }
+                    self.next_char();
+                    let c = char::from_u32(self.parse_hex4()?).ok_or("parse invalid identifier escape")?;
+                    let valid = c.is_alphabetic() || c == '_' || c == '$' || !s.is_empty() && c.is_alphanumeric();
+                    if !valid {
+                        return Err("parse invalid identifier escape");
+                    }
+                    s.push(c);
+                }
+                Some(c) if c.is_alphanumeric() || c == '_' || c == '$' => {
+                    s.push(c);
+                    self.next_char();
+                }
+                _ => return Ok(s),
+            }
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String, &'static str>
+    {
+        match self.ch {
+            Some('"') => self.parse_string(),
+            Some('\'') if self.json5 => self.parse_string(),
+            Some(c) if self.json5 && (c.is_alphabetic() || c == '_' || c == '$' || c == '\\') => self.parse_identifier(),
+            _ => Err("parse miss key"),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, &'static str>
+    {
+        let mut arr: Vec<Box<Json>> = vec![];
+        self.next_char();
+        self.parse_whitespace()?;
+        if self.ch == Some(']') {
+            self.next_char();
+            return Ok(Json::ARRAY(arr));
+        }
+        loop {
+            self.parse_whitespace()?;
+            let res = self.parse_value();
+            match res {
+                Ok(x) => {
+                    arr.push(Box::new(x));
+                    self.parse_whitespace()?;
+                    match self.ch {
+                        Some(']') => {
+                            self.next_char();
+                            break;
+                        }
+                        Some(',') => {
+                            self.next_char();
+                            // JSON5 允许最后一个元素后面有逗号
+                            self.parse_whitespace()?;
+                            if self.json5 && self.ch == Some(']') {
+                                self.next_char();
+                                break;
+                            }
+                            continue;
+                        }
+                        _ => return Err("parse invalid array"),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Json::ARRAY(arr))
+    }
+
+    fn parse_object(&mut self) -> Result<Json, &'static str>
+    {
+        let mut h: HashMap<String, Box<Json>> = HashMap::new();
+        self.next_char();
+        self.parse_whitespace()?;
+        if self.ch == Some('}') {
+            self.next_char();
+            return Ok(Json::OBJECT(h));
+        }
+        loop {
+            self.parse_whitespace()?;
+            let k = self.parse_key()?;
+            self.parse_whitespace()?;
+            match self.ch {
+                Some(':') => {
+                    self.next_char();
+                    self.parse_whitespace()?;
+                    let v = self.parse_value()?;
+                    h.insert(k, Box::new(v));
+                    self.parse_whitespace()?;
+                    match self.ch {
+                        Some(',') => {
+                            self.next_char();
+                            self.parse_whitespace()?;
+                            if self.json5 && self.ch == Some('}') {
+                                self.next_char();
+                                break;
+                            }
+                            continue;
+                        }
+                        Some('}') => {
+                            self.next_char();
+                            break;
+                        }
+                        None => return Err("parse invalid object"),
+                        _ => return Err("parse invalid object"),
+                    }
+                }
+                _ => return Err("parse miss colon"),
+            }
+        }
+        Ok(Json::OBJECT(h))
+    }
+
+    // 解析完整的 JSON 文本，值后面除了空白不能再有其它字符
+    pub(crate) fn parse_document(&mut self) -> Result<Json, &'static str>
+    {
+        self.parse_whitespace()?;
+        let v = self.parse_value()?;
+        self.parse_whitespace()?;
+        if self.ch.is_some() {
+            return Err("parse root not singular");
+        }
+        Ok(v)
+    }
+
+}
+
+/// Parses a complete JSON text from any supported input source.
+pub fn parse<I: Input>(input: I) -> Result<Json, ParseError> {
+    input.parse_with(&ParseOptions::default())
+}
+
+pub fn parse_with<I: Input>(input: I, options: &ParseOptions) -> Result<Json, ParseError> {
+    input.parse_with(options)
+}
+
+/// Like `parse_with`, and also records the byte range, line and column of every value and key.
+///
+/// Offsets index the input as given; for UTF-16 and UTF-32 input they index its UTF-8 transcoding.
+/// JSON5 is not supported, since only `ByteParser` records spans.
+pub fn parse_with_spans<I: Input>(input: I, options: &ParseOptions) -> Result<(Json, SpanMap), ParseError> {
+    input.parse_with_spans(options)
+}
+
+// 语法错误沿用解析器内部的 &'static str，I/O 错误单独区分出来
+// 按行读取的输入（比如 NDJSON）用 Line 标出出错的行号，JSON 文本序列用 Record 标出第几条记录
+#[derive(Debug)]
+pub enum ParseError {
+    Syntax(&'static str),
+    Io(io::Error),
+    Line(usize, Box<ParseError>),
+    Record(usize, Box<ParseError>),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Syntax(e) => f.write_str(e),
+            ParseError::Io(e) => write!(f, "io error: {}", e),
+            ParseError::Line(line, e) => write!(f, "line {}: {}", line, e),
+            ParseError::Record(record, e) => write!(f, "record {}: {}", record, e),
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ParseError::Syntax(_) => None,
+            ParseError::Io(e) => Some(e),
+            ParseError::Line(_, e) | ParseError::Record(_, e) => Some(&**e),
+        }
+    }
+}
+
+impl From<&'static str> for ParseError {
+    fn from(e: &'static str) -> Self {
+        ParseError::Syntax(e)
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+// 紧凑格式输出，不带任何空白
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::NULL => f.write_str("null"),
+            Json::TRUE => f.write_str("true"),
+            Json::FALSE => f.write_str("false"),
+            Json::NUMBER(n) => write_number(f, *n),
+            Json::STRING(s) => write_escaped_str(f, s),
+            Json::ARRAY(arr) => {
+                f.write_str("[")?;
+                for (i, v) in arr.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_str("]")
+            }
+            Json::OBJECT(h) => {
+                f.write_str("{")?;
+                for (i, (k, v)) in h.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_escaped_str(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+// JSON path 的写法：$.name、$.items[2]，不是标识符的 key 写成 $["a b"]
+pub(crate) fn path_key(path: &str, key: &str) -> String {
+    let mut chars = key.chars();
+    let ident = matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    let mut s = String::from(path);
+    if ident {
+        s.push('.');
+        s.push_str(key);
+    } else {
+        s.push('[');
+        // 写入 String 不会失败
+        write_escaped_str(&mut s, key).unwrap();
+        s.push(']');
+    }
+    s
+}
+
+pub(crate) fn path_index(path: &str, i: usize) -> String {
+    format!("{}[{}]", path, i)
+}
+
+/// Writes `n` as a JSON number.
+///
+/// JSON has no NaN or infinities (JSON5 input can produce them), so those are written as `null`.
+pub(crate) fn write_number<W: fmt::Write>(out: &mut W, n: f64) -> fmt::Result {
+    if n.is_finite() {
+        write!(out, "{}", n)
+    } else {
+        out.write_str("null")
+    }
+}
+
+/// Writes `s` as a quoted JSON string, escaping `"`, `\` and control characters.
+pub(crate) fn write_escaped_str<W: fmt::Write>(out: &mut W, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\u{c}' => out.write_str("\\f")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        // 超出 u32 范围的整数以前会溢出
+        let s = "[0, 42, 4294967296]";
+        let expect = Json::ARRAY(vec![
+            Box::new(Json::NUMBER(0.0)),
+            Box::new(Json::NUMBER(42.0)),
+            Box::new(Json::NUMBER(4294967296.0)),
+        ]);
+        assert_eq!(parse_str(s), Ok(expect));
+    }
+
+    fn parse_str(s: &str) -> Result<Json, &'static str> {
+        Parser::new(s.chars()).parse_document()
+    }
+
+    #[test]
+    fn test_parse_grammar() {
+        assert_eq!(parse_str("-0"), Ok(Json::NUMBER(-0.0)));
+        assert_eq!(parse_str("-12.5e-1"), Ok(Json::NUMBER(-1.25)));
+        assert_eq!(parse_str("1E+2"), Ok(Json::NUMBER(100.0)));
+        assert_eq!(parse_str("0.25"), Ok(Json::NUMBER(0.25)));
+        assert_eq!(
+            parse_str(r#""a\"\\\/\b\f\n\r\t\u00e9\ud83d\ude00""#),
+            Ok(Json::STRING("a\"\\/\u{8}\u{c}\n\r\t\u{e9}\u{1f600}".to_string()))
+        );
+        assert_eq!(parse_str(" [ ] "), Ok(Json::ARRAY(vec![])));
+        assert_eq!(parse_str("{ }"), Ok(Json::OBJECT(HashMap::new())));
+        let mut h = HashMap::new();
+        h.insert("k".to_string(), Box::new(Json::ARRAY(vec![Box::new(Json::NULL)])));
+        assert_eq!(parse_str(r#"{"k": [null]}"#), Ok(Json::OBJECT(h)));
+    }
+
+    #[test]
+    fn test_parse_grammar_errors() {
+        assert_eq!(parse_str("-"), Err("parse invalid number"));
+        assert_eq!(parse_str("1."), Err("parse invalid number"));
+        assert_eq!(parse_str("1e"), Err("parse invalid number"));
+        assert_eq!(parse_str("01"), Err("parse root not singular"));
+        assert_eq!(parse_str("1e999"), Err("parse number too big"));
+        assert_eq!(parse_str(r#""\x""#), Err("parse invalid string escape"));
+        assert_eq!(parse_str(r#""\u12""#), Err("parse invalid unicode hex"));
+        assert_eq!(parse_str(r#""\udc00""#), Err("parse invalid unicode surrogate"));
+        assert_eq!(parse_str(r#""\ud800x""#), Err("parse invalid unicode surrogate"));
+        assert_eq!(parse_str("\"a\nb\""), Err("parse invalid string char"));
+        assert_eq!(parse_str("\"abc"), Err("parse miss quotation mark"));
+        assert_eq!(parse_str("{1: 2}"), Err("parse miss key"));
+        assert_eq!(parse_str(r#"{"a" 1}"#), Err("parse miss colon"));
+        assert_eq!(parse_str("[1] x"), Err("parse root not singular"));
+    }
+
+    #[test]
+    fn test_parse_max_depth() {
+        let options = ParseOptions {
+            max_depth: 2,
+            ..ParseOptions::default()
+        };
+        let parse_depth = |s: &str| Parser::with_options(s.chars(), &options).parse_document();
+        assert!(parse_depth("[{}]").is_ok());
+        assert_eq!(parse_depth("[{\"a\": []}]"), Err("parse nesting too deep"));
+        assert_eq!(parse_str(&"[".repeat(129)), Err("parse nesting too deep"));
+    }
+
+    fn json5(s: &str) -> Result<Json, ParseError> {
+        let options = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        parse_with(s, &options)
+    }
+
+    #[test]
+    fn test_parse_json5() {
+        let input = "// config\n{\n  name: 'demo', /* inline */ $id: 0x1F,\n  'ratio': .5, half: 5., pos: +1,\n  big: -Infinity, text: 'a\\\n b\\x41\\'',\n  list: [1, 2,],\n}\n";
+        let expect = parse(r#"{"name": "demo", "$id": 31, "ratio": 0.5, "half": 5, "pos": 1, "list": [1, 2], "text": "a bA'", "big": 0}"#).unwrap();
+        let mut got = json5(input).unwrap();
+        match &mut got {
+            Json::OBJECT(h) => {
+                assert_eq!(*h["big"], Json::NUMBER(f64::NEG_INFINITY));
+                h.insert("big".to_string(), Box::new(Json::NUMBER(0.0)));
+            }
+            _ => panic!("expect object"),
+        }
+        assert_eq!(got, expect);
+        assert!(matches!(json5("NaN"), Ok(Json::NUMBER(n)) if n.is_nan()));
+        // 不带引号的 key 里可以有 \uXXXX 转义
+        assert_eq!(json5("{a\\u0062: 1, \\u0024x\\u00e9: 2}").unwrap(), parse(r#"{"ab": 1, "$xé": 2}"#).unwrap());
+    }
+
+    #[test]
+    fn test_write_non_finite() {
+        let json = json5("[NaN, Infinity, -Infinity, 1.5]").unwrap();
+        assert_eq!(json.to_string(), "[null,null,null,1.5]");
+        let mut writer = NdjsonWriter::new(vec![]);
+        writer.write(&json).unwrap();
+        let back: Vec<Json> = NdjsonReader::new(&writer.into_inner()[..]).map(Result::unwrap).collect();
+        assert_eq!(back, vec![parse("[null, null, null, 1.5]").unwrap()]);
+        let mut writer = JsonSeqWriter::new(vec![]);
+        writer.write(&json).unwrap();
+        assert!(JsonSeqReader::new(&writer.into_inner()[..]).all(|r| r.is_ok()));
+    }
+
+    #[test]
+    fn test_parse_json5_errors() {
+        assert!(matches!(json5("[1] /* x"), Err(ParseError::Syntax("parse unterminated comment"))));
+        assert!(matches!(json5("[1] / x"), Err(ParseError::Syntax("parse invalid comment"))));
+        assert!(matches!(json5("."), Err(ParseError::Syntax("parse invalid number"))));
+        assert!(matches!(json5("0x"), Err(ParseError::Syntax("parse invalid number"))));
+        assert!(matches!(json5("'a\nb'"), Err(ParseError::Syntax("parse invalid string char"))));
+        assert!(matches!(json5("{1: 2}"), Err(ParseError::Syntax("parse miss key"))));
+        assert!(matches!(json5("[1,,]"), Err(ParseError::Syntax("parse value error"))));
+        assert!(matches!(json5("{a\\x62: 1}"), Err(ParseError::Syntax("parse invalid identifier escape"))));
+        assert!(matches!(json5("{\\u0031: 1}"), Err(ParseError::Syntax("parse invalid identifier escape"))));
+        assert!(matches!(json5("{a\\u002d: 1}"), Err(ParseError::Syntax("parse invalid identifier escape"))));
+        assert!(matches!(json5("{a\\u00: 1}"), Err(ParseError::Syntax("parse invalid unicode hex"))));
+        // 不打开 JSON5 时仍然是严格的语法
+        assert!(matches!(parse("{a: 1}"), Err(ParseError::Syntax("parse miss key"))));
+        assert!(matches!(parse("[1,]"), Err(ParseError::Syntax("parse value error"))));
+    }
+
+    #[test]
+    fn test_parse_jsonc() {
+        let options = ParseOptions {
+            jsonc: true,
+            ..ParseOptions::default()
+        };
+        let input = "{\n  // compiler\n  \"strict\": true, /* see docs */ \"target\": \"es2020\"\n} // end";
+        let got = parse_with(input, &options).unwrap();
+        assert_eq!(got, parse(r#"{"strict": true, "target": "es2020"}"#).unwrap());
+        assert!(parse_with(input.as_bytes(), &options).is_ok());
+        let err = |s: &str| parse_with(s, &options).unwrap_err().to_string();
+        assert_eq!(err("[1 /* x */, 2 /*"), "parse unterminated comment");
+        assert_eq!(err("[1, 2] /"), "parse invalid comment");
+        // 注释以外仍然是严格的 JSON
+        assert_eq!(err("{\"a\": 1,}"), "parse miss key");
+        assert_eq!(err("'a'"), "parse value error");
+        assert!(parse("[1] // x").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+// json! 宏：用接近 JSON 的写法构造 Json，值的位置可以写任意 Rust 表达式
+// 写法参考 serde_json 的 json!，逐个 token 处理（tt muncher）：
+// null、true、false、[...]、{...} 按 JSON 处理，其余的一直取到逗号为止当作表达式，用 Json::from 转换
+// key 是逗号之后、冒号之前的所有 token，通常是字符串字面量，也可以是 String 或 &str 类型的表达式
+
+/// Builds a `Json` from JSON-like syntax, with Rust expressions allowed in value and key position.
+///
+/// ```
+/// use json_parser::json;
+///
+/// let id = 7;
+/// let v = json!({"id": id, "tags": ["a", "b"], "ok": true, "parent": null});
+/// assert_eq!(v["tags"][1].as_str(), Some("b"));
+/// ```
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::Json::NULL
+    };
+    (true) => {
+        $crate::Json::TRUE
+    };
+    (false) => {
+        $crate::Json::FALSE
+    };
+    ([]) => {
+        $crate::Json::ARRAY(::std::vec::Vec::new())
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::Json::ARRAY($crate::json_internal!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::Json::OBJECT(::std::collections::HashMap::new())
+    };
+    ({ $($tt:tt)+ }) => {{
+        let mut object = ::std::collections::HashMap::new();
+        $crate::json_internal!(@object object () ($($tt)+));
+        $crate::Json::OBJECT(object)
+    }};
+    ($other:expr) => {
+        $crate::Json::from($other)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // 数组：已经处理好的元素放在 [] 里
+    (@array [$($elems:expr,)*]) => {
+        ::std::vec![$($elems,)*]
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* ::std::boxed::Box::new($crate::json!(null)),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* ::std::boxed::Box::new($crate::json!(true)),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* ::std::boxed::Box::new($crate::json!(false)),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* ::std::boxed::Box::new($crate::json!([$($array)*])),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* ::std::boxed::Box::new($crate::json!({$($object)*})),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* ::std::boxed::Box::new($crate::json!($next)),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json_internal!(@array [$($elems,)* ::std::boxed::Box::new($crate::json!($last)),])
+    };
+    (@array [$($elems:expr,)*] , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // 对象：() 里是正在收集的 key，[] 里是已经收集完的 key
+    (@object $object:ident () ()) => {};
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        $object.insert(::std::string::String::from($($key)+), ::std::boxed::Box::new($value));
+        $crate::json_internal!(@object $object () ($($rest)*));
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        $object.insert(::std::string::String::from($($key)+), ::std::boxed::Box::new($value));
+    };
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json!(null)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: true $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json!(true)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: false $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json!(false)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json!([$($array)*])) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json!({$($map)*})) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json!($value)) , $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json!($value)));
+    };
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object ($($key)* $tt) ($($rest)*));
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Json;
+
+    #[test]
+    fn test_json_macro() {
+        let id = 7;
+        let name = String::from("n");
+        let key = "dyn";
+        let v = json!({
+            "id": id,
+            "name": name,
+            "tags": ["a", "b", [], {}],
+            "ok": true,
+            "nested": {"x": null, "y": [1.5, -2, false],},
+            key: id * 2,
+        });
+        let expect = crate::parse(
+            r#"{"id": 7, "name": "n", "tags": ["a", "b", [], {}], "ok": true,
+                "nested": {"x": null, "y": [1.5, -2, false]}, "dyn": 14}"#,
+        )
+        .unwrap();
+        assert_eq!(v, expect);
+        assert_eq!(json!(null), Json::NULL);
+        assert_eq!(json!([1, [2, [3]]]), crate::parse("[1, [2, [3]]]").unwrap());
+        assert_eq!(json!(v["id"].clone()), Json::NUMBER(7.0));
+    }
+}
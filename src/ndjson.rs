@@ -0,0 +1,139 @@
+// NDJSON / JSON Lines：每行一个 JSON 值，行之间用 \n 分隔（允许 \r\n）
+// 某一行解析失败只影响这一行，迭代器会继续读后面的行；空行直接跳过
+// 只有第一行开头的 UTF-8 BOM 会被去掉，其余行里的 BOM 按语法错误处理
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::{input, Json, ParseError, ParseOptions};
+
+/// Reads one JSON value per line.
+///
+/// Syntax errors are reported as `ParseError::Line` and iteration continues with the next line.
+/// An I/O error ends the iteration.
+pub struct NdjsonReader<R: Read> {
+    reader: BufReader<R>,
+    options: ParseOptions,
+    buf: Vec<u8>,
+    line: usize,
+    done: bool,
+}
+
+impl<R: Read> NdjsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        NdjsonReader::with_options(reader, &ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: &ParseOptions) -> Self {
+        NdjsonReader {
+            reader: BufReader::new(reader),
+            options: options.clone(),
+            buf: vec![],
+            line: 0,
+            done: false,
+        }
+    }
+
+    /// Line number (starting at 1) of the most recently returned item.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl<R: Read> Iterator for NdjsonReader<R> {
+    type Item = Result<Json, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    self.line += 1;
+                    let mut text = &self.buf[..];
+                    if self.line == 1 {
+                        text = text.strip_prefix(b"\xef\xbb\xbf").unwrap_or(text);
+                    }
+                    while let Some((b'\n', rest)) | Some((b'\r', rest)) = text.split_last() {
+                        text = rest;
+                    }
+                    if text.iter().all(|b| matches!(b, b' ' | b'\t')) {
+                        continue;
+                    }
+                    let res = input::parse_utf8(text, &self.options);
+                    return Some(res.map_err(|e| ParseError::Line(self.line, Box::new(ParseError::Syntax(e)))));
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(ParseError::Io(e)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Writes one compact JSON value per line.
+pub struct NdjsonWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        NdjsonWriter { out }
+    }
+
+    pub fn write(&mut self, json: &Json) -> io::Result<()> {
+        // 紧凑格式里字符串中的换行都会被转义，所以一个值一定只占一行
+        writeln!(self.out, "{}", json)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_lines() {
+        let input = "{\"a\": 1}\r\n\n[1, 2\n\"ok\"\n";
+        let mut reader = NdjsonReader::new(input.as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap(), crate::parse("{\"a\": 1}").unwrap());
+        assert_eq!(reader.line(), 1);
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "line 3: parse invalid array");
+        assert_eq!(reader.next().unwrap().unwrap(), Json::STRING("ok".to_string()));
+        assert_eq!(reader.line(), 4);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_read_bom() {
+        let mut reader = NdjsonReader::new("\u{feff}1\n\u{feff}2\n".as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap(), Json::NUMBER(1.0));
+        assert_eq!(reader.next().unwrap().unwrap_err().to_string(), "line 2: parse value error");
+        let mut reader = NdjsonReader::new("\u{feff}\n[]".as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap(), Json::ARRAY(vec![]));
+        assert_eq!(reader.line(), 2);
+    }
+
+    #[test]
+    fn test_write_lines() {
+        let values = vec![crate::parse("{\"a\": \"x\\ny\"}").unwrap(), Json::NUMBER(2.0)];
+        let mut writer = NdjsonWriter::new(vec![]);
+        for v in values.iter() {
+            writer.write(v).unwrap();
+        }
+        let out = writer.into_inner();
+        assert_eq!(out, b"{\"a\":\"x\\ny\"}\n2\n");
+        let back: Vec<Json> = NdjsonReader::new(&out[..]).map(Result::unwrap).collect();
+        assert_eq!(back, values);
+    }
+}
@@ -0,0 +1,463 @@
+// 解析不完整的 JSON 前缀，比如正在流式输出的 {"name":"Al
+// 输入在哪里结束，就把那里还没闭合的字符串、数组和对象补上，得到一个可以直接使用的值：
+// - 没写完的字符串保留已有的内容，末尾不完整的转义和 UTF-8 字节丢掉
+// - 没写完的数字取最长的合法前缀，比如 1. 和 1e 都当作 1
+// - true/false/null 只要前缀对得上就补全
+// - 对象里只写了一半的 key，或者 key 后面还没有值的成员直接丢掉
+// - 写到输入末尾、本身已经合法的数字算作完整的
+// 所有被补全过的部分都会把它们的 JSON path 记录在 incomplete 里
+// 不是因为输入提前结束而出现的语法错误仍然按正常的错误返回
+//
+// 没写完的注释当作空白；JSON5 的字符串、数字和不带引号的 key 先在字节上找到结尾，
+// 再交给 char 的 Parser 解析，这样和 parse_with 的结果一致
+
+use std::collections::HashMap;
+use std::str;
+
+use crate::input::parse_chars;
+use crate::lexer::comment_end;
+use crate::{path_index, path_key, ByteParser, Json, ParseError, ParseOptions};
+
+/// The best-effort value of a JSON prefix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partial {
+    pub value: Json,
+    /// Paths of the values that were cut off by the end of input and closed automatically.
+    pub incomplete: Vec<String>,
+}
+
+impl Partial {
+    pub fn is_complete(&self) -> bool {
+        self.incomplete.is_empty()
+    }
+}
+
+struct PartialParser<'a> {
+    json_bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+    options: &'a ParseOptions,
+    incomplete: Vec<String>,
+}
+
+// 返回闭合引号之后的位置，字符串没有结束时返回 None
+fn string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == bytes[start] => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+impl<'a> PartialParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.json_bytes.get(self.pos).copied()
+    }
+
+    // 当前位置的字符，末尾不完整或者不合法的 UTF-8 返回 None
+    fn peek_char(&self) -> Option<char> {
+        let bytes = &self.json_bytes[self.pos..self.json_bytes.len().min(self.pos + 4)];
+        let valid = match str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => str::from_utf8(&bytes[..e.valid_up_to()]).unwrap(),
+        };
+        valid.chars().next()
+    }
+
+    fn parse_whitespace(&mut self) -> Result<(), &'static str> {
+        loop {
+            match self.peek() {
+                Some(b' ') | Some(b'\n') | Some(b'\r') | Some(b'\t') => self.pos += 1,
+                Some(b'/') if self.options.jsonc || self.options.json5 => {
+                    self.pos = match comment_end(self.json_bytes, self.pos) {
+                        Ok(end) => end,
+                        // 注释被输入末尾截断
+                        Err("parse unterminated comment") => self.json_bytes.len(),
+                        Err(_) if self.pos + 1 == self.json_bytes.len() => self.json_bytes.len(),
+                        Err(e) => return Err(e),
+                    }
+                }
+                Some(_) if self.options.json5 => match self.peek_char() {
+                    Some(c) if c.is_whitespace() || c == '\u{feff}' => self.pos += c.len_utf8(),
+                    // 输入末尾只写了一半的字符
+                    None if str::from_utf8(&self.json_bytes[self.pos..]).is_err_and(|e| e.error_len().is_none()) => {
+                        self.pos = self.json_bytes.len()
+                    }
+                    _ => return Ok(()),
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    // 一个写完了的字符串、数字或字面量，返回值和它的长度
+    fn scalar(&self, bytes: &[u8]) -> Result<(Json, usize), &'static str> {
+        if self.options.json5 {
+            parse_chars(bytes, self.options, |p| p.parse_value())
+        } else {
+            ByteParser::scalar(bytes)
+        }
+    }
+
+    // 没有值可用（输入在值开始之前就结束了）时返回 None
+    fn parse_value(&mut self, path: &str) -> Result<Option<Json>, &'static str> {
+        self.parse_whitespace()?;
+        let json5 = self.options.json5;
+        let (v, complete) = match self.peek() {
+            None => return Ok(None),
+            Some(q) if q == b'"' || json5 && q == b'\'' => {
+                let (s, complete) = self.parse_string()?;
+                (Json::STRING(s), complete)
+            }
+            Some(b'[') => return self.parse_nested(path, Self::parse_array).map(Some),
+            Some(b'{') => return self.parse_nested(path, Self::parse_object).map(Some),
+            Some(b't') => self.parse_literals("true", Json::TRUE)?,
+            Some(b'f') => self.parse_literals("false", Json::FALSE)?,
+            Some(b'n') => self.parse_literals("null", Json::NULL)?,
+            Some(b'-') | Some(b'0'..=b'9') => match self.parse_number()? {
+                Some(v) => v,
+                None => return Ok(None),
+            },
+            Some(b'+') | Some(b'.') | Some(b'I') | Some(b'N') if json5 => match self.parse_number()? {
+                Some(v) => v,
+                None => return Ok(None),
+            },
+            Some(_) => return Err("parse value error"),
+        };
+        if !complete {
+            self.incomplete.push(path.to_string());
+        }
+        Ok(Some(v))
+    }
+
+    fn parse_nested<F>(&mut self, path: &str, f: F) -> Result<Json, &'static str>
+    where
+        F: FnOnce(&mut Self, &str) -> Result<Json, &'static str>,
+    {
+        if self.depth >= self.options.max_depth {
+            return Err("parse nesting too deep");
+        }
+        self.depth += 1;
+        let res = f(self, path);
+        self.depth -= 1;
+        res
+    }
+
+    fn parse_literals(&mut self, s: &str, v: Json) -> Result<(Json, bool), &'static str> {
+        let rest = &self.json_bytes[self.pos..];
+        if rest.starts_with(s.as_bytes()) {
+            self.pos += s.len();
+            Ok((v, true))
+        } else if s.as_bytes().starts_with(rest) {
+            self.pos = self.json_bytes.len();
+            Ok((v, false))
+        } else {
+            Err("parse invalid literal")
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Option<(Json, bool)>, &'static str> {
+        let start = self.pos;
+        // JSON5 的数字还可以是十六进制、Infinity 和 NaN
+        let json5 = self.options.json5;
+        while let Some(b) = self.peek() {
+            if !(matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') || json5 && b.is_ascii_alphanumeric()) {
+                break;
+            }
+            self.pos += 1;
+        }
+        if self.peek().is_some() {
+            // 后面还有别的字符，数字已经写完了，按 parse 的规则切分，和 parse 的报错一致
+            let (v, len) = self.scalar(&self.json_bytes[start..])?;
+            self.pos = start + len;
+            return Ok(Some((v, true)));
+        }
+        let text = &self.json_bytes[start..self.pos];
+        let name = text.strip_prefix(b"-").or_else(|| text.strip_prefix(b"+")).unwrap_or(text);
+        if json5 && !name.is_empty() && name.len() < 3 && b"NaN".starts_with(name) {
+            return Ok(Some((Json::NUMBER(f64::NAN), false)));
+        }
+        if json5 && !name.is_empty() && name.len() < 8 && b"Infinity".starts_with(name) {
+            let n = if text[0] == b'-' { f64::NEG_INFINITY } else { f64::INFINITY };
+            return Ok(Some((Json::NUMBER(n), false)));
+        }
+        // 数字一直写到了输入末尾，本身合法就是完整的，否则可能还没写完，取最长的合法前缀
+        for end in (1..=text.len()).rev() {
+            if let Ok((v, len)) = self.scalar(&text[..end]) {
+                if len == end {
+                    return Ok(Some((v, end == text.len())));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse_string(&mut self) -> Result<(String, bool), &'static str> {
+        let start = self.pos;
+        if let Some(end) = string_end(self.json_bytes, start) {
+            self.pos = end;
+            return match self.scalar(&self.json_bytes[start..end])? {
+                (Json::STRING(s), _) => Ok((s, true)),
+                _ => Err("parse miss quotation mark"),
+            };
+        }
+        // 补上闭合引号；末尾不完整的转义（最长是 \uXXXX\uXX）和 UTF-8 字节要先去掉
+        let text = &self.json_bytes[start..];
+        self.pos = self.json_bytes.len();
+        let mut first_err = None;
+        for cut in 0..text.len().min(12) {
+            let mut closed = text[..text.len() - cut].to_vec();
+            closed.push(text[0]);
+            match self.scalar(&closed) {
+                Ok((Json::STRING(s), len)) if len == closed.len() => return Ok((s, false)),
+                Ok(_) => {}
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_err.unwrap_or("parse miss quotation mark"))
+    }
+
+    // key 被输入末尾截断时返回 None；JSON5 不带引号的 key 写到末尾时也可能还没写完
+    fn parse_key(&mut self) -> Result<Option<String>, &'static str> {
+        let json5 = self.options.json5;
+        match self.peek() {
+            Some(q) if q == b'"' || json5 && q == b'\'' => {
+                let (k, complete) = self.parse_string()?;
+                Ok(if complete { Some(k) } else { None })
+            }
+            Some(_) if json5 => {
+                let start = self.pos;
+                while let Some(b) = self.peek() {
+                    if !(b.is_ascii_alphanumeric() || matches!(b, b'_' | b'$' | b'\\') || b >= 0x80) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                if self.peek().is_none() {
+                    return Ok(None);
+                }
+                let (k, len) = parse_chars(&self.json_bytes[start..], self.options, |p| p.parse_key())?;
+                self.pos = start + len;
+                Ok(Some(k))
+            }
+            _ => Err("parse miss key"),
+        }
+    }
+
+    fn parse_array(&mut self, path: &str) -> Result<Json, &'static str> {
+        let mut arr: Vec<Box<Json>> = vec![];
+        let mut complete = false;
+        self.pos += 1;
+        self.parse_whitespace()?;
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::ARRAY(arr));
+        }
+        loop {
+            // JSON5 允许最后一个元素后面有逗号
+            if self.options.json5 && !arr.is_empty() {
+                self.parse_whitespace()?;
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    complete = true;
+                    break;
+                }
+            }
+            let child = path_index(path, arr.len());
+            match self.parse_value(&child)? {
+                Some(v) => arr.push(Box::new(v)),
+                None => break,
+            }
+            self.parse_whitespace()?;
+            match self.peek() {
+                Some(b']') => {
+                    self.pos += 1;
+                    complete = true;
+                    break;
+                }
+                Some(b',') => self.pos += 1,
+                None => break,
+                _ => return Err("parse invalid array"),
+            }
+        }
+        if !complete {
+            self.incomplete.push(path.to_string());
+        }
+        Ok(Json::ARRAY(arr))
+    }
+
+    fn parse_object(&mut self, path: &str) -> Result<Json, &'static str> {
+        let mut h: HashMap<String, Box<Json>> = HashMap::new();
+        let mut complete = false;
+        self.pos += 1;
+        self.parse_whitespace()?;
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::OBJECT(h));
+        }
+        let mut first = true;
+        loop {
+            self.parse_whitespace()?;
+            let k = match self.peek() {
+                None => break,
+                Some(b'}') if self.options.json5 && !first => {
+                    self.pos += 1;
+                    complete = true;
+                    break;
+                }
+                _ => match self.parse_key()? {
+                    Some(k) => k,
+                    None => break,
+                },
+            };
+            first = false;
+            self.parse_whitespace()?;
+            match self.peek() {
+                None => break,
+                Some(b':') => self.pos += 1,
+                Some(_) => return Err("parse miss colon"),
+            }
+            let child = path_key(path, &k);
+            match self.parse_value(&child)? {
+                Some(v) => {
+                    h.insert(k, Box::new(v));
+                }
+                None => break,
+            }
+            self.parse_whitespace()?;
+            match self.peek() {
+                Some(b'}') => {
+                    self.pos += 1;
+                    complete = true;
+                    break;
+                }
+                Some(b',') => self.pos += 1,
+                None => break,
+                _ => return Err("parse invalid object"),
+            }
+        }
+        if !complete {
+            self.incomplete.push(path.to_string());
+        }
+        Ok(Json::OBJECT(h))
+    }
+}
+
+/// Parses a possibly truncated JSON text, closing whatever the end of input left open.
+pub fn parse_partial(bytes: &[u8]) -> Result<Partial, ParseError> {
+    parse_partial_with(bytes, &ParseOptions::default())
+}
+
+/// Like `parse_partial`, with the nesting limit and the JSONC or JSON5 syntax of `options`.
+///
+/// The input must be UTF-8; a leading BOM is skipped as `parse` does.
+pub fn parse_partial_with(bytes: &[u8], options: &ParseOptions) -> Result<Partial, ParseError> {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    let mut parser = PartialParser {
+        json_bytes: bytes,
+        pos: 0,
+        depth: 0,
+        options,
+        incomplete: vec![],
+    };
+    let value = parser.parse_value("$")?.ok_or("expect value error")?;
+    parser.parse_whitespace()?;
+    if parser.peek().is_some() {
+        return Err(ParseError::Syntax("parse root not singular"));
+    }
+    // 先记录的是内层，改成从外到内的顺序
+    parser.incomplete.reverse();
+    Ok(Partial {
+        value,
+        incomplete: parser.incomplete,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial(s: &str) -> Partial {
+        parse_partial(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_partial() {
+        let p = partial("{\"name\":\"Al");
+        assert_eq!(p.value, crate::parse("{\"name\":\"Al\"}").unwrap());
+        assert_eq!(p.incomplete, vec!["$".to_string(), "$.name".to_string()]);
+
+        let p = partial("{\"a\": [1, 2.5e");
+        assert_eq!(p.value, crate::parse("{\"a\": [1, 2.5]}").unwrap());
+        assert_eq!(p.incomplete, vec!["$", "$.a", "$.a[1]"]);
+
+        assert_eq!(partial("[tr").value, crate::parse("[true]").unwrap());
+        assert_eq!(partial("{\"a\": 1, \"b").value, crate::parse("{\"a\": 1}").unwrap());
+        assert_eq!(partial("{\"a\": 1, \"b\":").value, crate::parse("{\"a\": 1}").unwrap());
+        assert_eq!(partial("[\"x\\u00").value, crate::parse("[\"x\"]").unwrap());
+        assert_eq!(partial("[-").value, crate::parse("[]").unwrap());
+
+        let p = partial(" {\"a\": [1, {}]} ");
+        assert!(p.is_complete());
+        // 写到末尾的数字本身合法就是完整的
+        assert!(partial("1").is_complete());
+        assert_eq!(partial("[1, -2.5e3").incomplete, vec!["$"]);
+        assert_eq!(partial("1.").incomplete, vec!["$"]);
+        // 和 parse 一样跳过开头的 BOM
+        assert_eq!(partial("\u{feff}[1").value, crate::parse("[1]").unwrap());
+    }
+
+    #[test]
+    fn test_parse_partial_with() {
+        let with = |s: &str, options: &ParseOptions| parse_partial_with(s.as_bytes(), options).unwrap();
+        let jsonc = ParseOptions {
+            jsonc: true,
+            ..ParseOptions::default()
+        };
+        let p = with("{\"a\": 1, // one\n \"b\": [2 /* two", &jsonc);
+        assert_eq!(p.value, crate::parse("{\"a\": 1, \"b\": [2]}").unwrap());
+        assert_eq!(p.incomplete, vec!["$", "$.b"]);
+        assert_eq!(with("[1 /", &jsonc).value, crate::parse("[1]").unwrap());
+        assert!(parse_partial_with(b"{\"a\": 1,", &jsonc).is_ok());
+        assert!(matches!(parse_partial_with(b"[1, ]", &jsonc), Err(ParseError::Syntax("parse value error"))));
+
+        let json5 = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        let input = "{a: 'x', \"b\": [0x10, +.5, Infinity,], c: {},} // end";
+        let p = with(input, &json5);
+        assert!(p.is_complete());
+        assert_eq!(p.value, crate::parse_with(input, &json5).unwrap());
+        let p = with("{a: 'it\\'s', b: [-Inf", &json5);
+        assert_eq!(p.value, crate::parse_with("{a: \"it's\", b: [-Infinity]}", &json5).unwrap());
+        assert_eq!(p.incomplete, vec!["$", "$.b", "$.b[0]"]);
+        assert_eq!(with("{a\\u0062: 1", &json5).value, crate::parse("{\"ab\": 1}").unwrap());
+        assert_eq!(with("{a: 1, bc", &json5).value, crate::parse("{\"a\": 1}").unwrap());
+        assert!(matches!(with("[Na", &json5).value, Json::ARRAY(v) if matches!(*v[0], Json::NUMBER(n) if n.is_nan())));
+        assert_eq!(parse_partial_with(b"[1,\xc2", &json5).unwrap().value, crate::parse("[1]").unwrap());
+        assert!(matches!(parse_partial_with(b"{1: 2", &json5), Err(ParseError::Syntax("parse miss key"))));
+
+        let shallow = ParseOptions {
+            max_depth: 1,
+            ..ParseOptions::default()
+        };
+        assert!(matches!(parse_partial_with(b"[[", &shallow), Err(ParseError::Syntax("parse nesting too deep"))));
+    }
+
+    #[test]
+    fn test_parse_partial_errors() {
+        assert!(matches!(parse_partial(b"[1 2"), Err(ParseError::Syntax("parse invalid array"))));
+        assert!(matches!(parse_partial(b"[1-2]"), Err(ParseError::Syntax("parse invalid array"))));
+        assert!(matches!(parse_partial(b"[1.]"), Err(ParseError::Syntax("parse invalid number"))));
+        assert!(matches!(parse_partial(b"{\"a\" 1"), Err(ParseError::Syntax("parse miss colon"))));
+        assert!(matches!(parse_partial(b"  "), Err(ParseError::Syntax("expect value error"))));
+        assert!(matches!(parse_partial(b"[1] x"), Err(ParseError::Syntax("parse root not singular"))));
+    }
+}
@@ -0,0 +1,176 @@
+// 从 io::Read 直接解析，不需要先把整个输入读进 String
+// 输入按块读进缓冲区，再逐个解码成 char 交给 Parser
+// 一个多字节字符可能被拆在两次 read 之间，解码时要先把剩下的字节补齐
+
+use std::io::{self, Read};
+use std::str;
+
+use crate::encoding::{self, Encoding};
+use crate::{input, Json, ParseError, ParseOptions, Parser};
+
+const BUF_SIZE: usize = 8 * 1024;
+
+/// Incrementally decodes UTF-8 from a reader into chars.
+///
+/// Iteration stops at the first I/O or decoding error, which is then available from `take_error`.
+pub struct Utf8Chars<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    error: Option<ParseError>,
+}
+
+impl<R: Read> Utf8Chars<R> {
+    pub fn new(reader: R) -> Self {
+        Utf8Chars {
+            reader,
+            buf: vec![0; BUF_SIZE],
+            start: 0,
+            end: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the error that ended iteration, if any.
+    pub fn take_error(&mut self) -> Option<ParseError> {
+        self.error.take()
+    }
+
+    // 把未消费的字节挪到缓冲区开头，再尽量读满剩下的空间
+    // 返回 false 表示已经读到输入末尾
+    fn fill(&mut self) -> io::Result<bool> {
+        self.buf.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        loop {
+            match self.reader.read(&mut self.buf[self.end..]) {
+                Ok(0) => return Ok(false),
+                Ok(n) => {
+                    self.end += n;
+                    return Ok(true);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn decode(&mut self) -> Result<Option<char>, ParseError> {
+        if self.start == self.end && !self.fill()? {
+            return Ok(None);
+        }
+        let width = match self.buf[self.start] {
+            0x00..=0x7F => 1,
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Err(ParseError::Syntax("parse invalid utf-8")),
+        };
+        while self.end - self.start < width {
+            if !self.fill()? {
+                return Err(ParseError::Syntax("parse invalid utf-8"));
+            }
+        }
+        let bytes = &self.buf[self.start..self.start + width];
+        let s = str::from_utf8(bytes).map_err(|_| ParseError::Syntax("parse invalid utf-8"))?;
+        self.start += width;
+        Ok(s.chars().next())
+    }
+}
+
+impl<R: Read> Iterator for Utf8Chars<R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.decode() {
+            Ok(ch) => ch,
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+}
+
+/// Parses a complete JSON text from a reader.
+///
+/// I/O failures are reported as `ParseError::Io`, everything else as `ParseError::Syntax`.
+pub fn from_reader<R: Read>(reader: R) -> Result<Json, ParseError> {
+    parse_reader(reader, &ParseOptions::default())
+}
+
+pub(crate) fn parse_reader<R: Read>(mut reader: R, options: &ParseOptions) -> Result<Json, ParseError> {
+    // 先读出最多 4 个字节判断编码
+    let mut head = [0; 4];
+    let mut n = 0;
+    while n < head.len() {
+        match reader.read(&mut head[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(ParseError::Io(e)),
+        }
+    }
+    let (enc, bom) = encoding::detect(&head[..n]);
+    if enc != Encoding::Utf8 {
+        // UTF-16/32 的输入很少见，直接整个读出来转码
+        let mut bytes = head[..n].to_vec();
+        reader.read_to_end(&mut bytes)?;
+        let bytes = encoding::to_utf8(&bytes, options)?;
+        return Ok(input::parse_utf8(&bytes, options)?);
+    }
+    let mut chars = Utf8Chars::new((&head[bom..n]).chain(reader));
+    let res = Parser::with_options(&mut chars, options).parse_document();
+    // 读取出错时解析器只会看到输入提前结束，所以先报告读取错误
+    if let Some(e) = chars.take_error() {
+        return Err(e);
+    }
+    res.map_err(ParseError::Syntax)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每次只返回一个字节，保证多字节字符一定会被拆开
+    struct OneByte<'a>(&'a [u8]);
+
+    impl<'a> Read for OneByte<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken pipe"))
+        }
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let input = "{\"\u{4e2d}\u{6587}\": [\"\u{1F600}\", -1.5e3]}";
+        let v = from_reader(OneByte(input.as_bytes())).unwrap();
+        assert_eq!(v, crate::parse_bytes(input.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_from_reader_errors() {
+        assert!(matches!(from_reader(Broken), Err(ParseError::Io(_))));
+        assert!(matches!(from_reader(OneByte(b"\"\xe4\xb8")), Err(ParseError::Syntax("parse invalid utf-8"))));
+        assert!(matches!(from_reader(OneByte(b"[1, 2")), Err(ParseError::Syntax("parse invalid array"))));
+    }
+}
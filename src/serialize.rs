@@ -0,0 +1,469 @@
+// 自己的序列化框架：ToJson 把 Rust 值转成 Json，FromJson 从 Json 读出 Rust 值
+// 和 From/TryFrom 不同，这两个 trait 只借用输入，而且可以给外部类型（比如 Rc、元组）实现
+//
+// FromJson 的错误带着出错位置的 JSON path，比如 $.items[2].price: expected number, found string
+// 错误在最内层产生，path 为空，每往外返回一层，容器就在前面补上自己的 key 或下标
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::{path_index, path_key, Json, TypeError};
+
+/// Converts a value into `Json`.
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+/// Reads a value out of `Json`.
+pub trait FromJson: Sized {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// A conversion failure, with the path of the value that could not be converted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromJsonError {
+    // 从内到外的顺序
+    steps: Vec<Step>,
+    message: String,
+}
+
+impl FromJsonError {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        FromJsonError {
+            steps: vec![],
+            message: message.into(),
+        }
+    }
+
+    /// The error for a value of the wrong type, e.g. `expected number, found string`.
+    pub fn expected(expected: &'static str, found: &Json) -> Self {
+        FromJsonError::from(TypeError::new(expected, found))
+    }
+
+    /// Marks the error as coming from the member `key` of an object.
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.steps.push(Step::Key(key.to_string()));
+        self
+    }
+
+    /// Marks the error as coming from the element `i` of an array.
+    pub fn with_index(mut self, i: usize) -> Self {
+        self.steps.push(Step::Index(i));
+        self
+    }
+
+    /// Path of the value that failed to convert, such as `$.items[2].price`.
+    pub fn path(&self) -> String {
+        let mut path = String::from("$");
+        for step in self.steps.iter().rev() {
+            path = match step {
+                Step::Key(k) => path_key(&path, k),
+                Step::Index(i) => path_index(&path, *i),
+            };
+        }
+        path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<TypeError> for FromJsonError {
+    fn from(e: TypeError) -> Self {
+        FromJsonError::new(e.to_string())
+    }
+}
+
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path(), self.message)
+    }
+}
+
+impl error::Error for FromJsonError {}
+
+impl ToJson for Json {
+    fn to_json(&self) -> Json {
+        self.clone()
+    }
+}
+
+impl FromJson for Json {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        Ok(json.clone())
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Json {
+        Json::from(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        json.as_bool().ok_or_else(|| FromJsonError::expected("boolean", json))
+    }
+}
+
+// 整数的范围检查沿用 TryFrom，类型不对时统一报 expected number
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl ToJson for $t {
+                fn to_json(&self) -> Json {
+                    Json::from(*self)
+                }
+            }
+
+            impl FromJson for $t {
+                fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+                    match json {
+                        Json::NUMBER(_) => Ok(<$t>::try_from(json.clone())?),
+                        _ => Err(FromJsonError::expected("number", json)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+impl ToJson for str {
+    fn to_json(&self) -> Json {
+        Json::from(self)
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Json {
+        Json::from(self)
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        json.as_str().map(str::to_string).ok_or_else(|| FromJsonError::expected("string", json))
+    }
+}
+
+// char 写成只有一个字符的字符串
+impl ToJson for char {
+    fn to_json(&self) -> Json {
+        Json::STRING(self.to_string())
+    }
+}
+
+impl FromJson for char {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let s = json.as_str().ok_or_else(|| FromJsonError::expected("string", json))?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(FromJsonError::new(format!("expected single character, found string of length {}", s.chars().count()))),
+        }
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        T::from_json(json).map(Box::new)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Rc<T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Rc<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        T::from_json(json).map(Rc::new)
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Json {
+        self.as_ref().map_or(Json::NULL, ToJson::to_json)
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::NULL => Ok(None),
+            _ => T::from_json(json).map(Some),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Json {
+        Json::ARRAY(self.iter().map(|x| Box::new(x.to_json())).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Json {
+        self[..].to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let arr = json.as_array().ok_or_else(|| FromJsonError::expected("array", json))?;
+        arr.iter()
+            .enumerate()
+            .map(|(i, x)| T::from_json(x).map_err(|e| e.with_index(i)))
+            .collect()
+    }
+}
+
+impl<T: ToJson, const N: usize> ToJson for [T; N] {
+    fn to_json(&self) -> Json {
+        self[..].to_json()
+    }
+}
+
+impl<T: FromJson, const N: usize> FromJson for [T; N] {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let v = Vec::<T>::from_json(json)?;
+        let len = v.len();
+        <[T; N]>::try_from(v).map_err(|_| FromJsonError::new(format!("expected array of length {}, found array of length {}", N, len)))
+    }
+}
+
+// 下面几个函数给 #[derive(ToJson, FromJson)] 生成的代码使用
+
+#[doc(hidden)]
+pub fn expect_object(json: &Json) -> Result<&HashMap<String, Box<Json>>, FromJsonError> {
+    json.as_object().ok_or_else(|| FromJsonError::expected("object", json))
+}
+
+#[doc(hidden)]
+#[allow(clippy::vec_box)]
+pub fn expect_array(json: &Json, len: usize) -> Result<&Vec<Box<Json>>, FromJsonError> {
+    let arr = json.as_array().ok_or_else(|| FromJsonError::expected("array", json))?;
+    if arr.len() != len {
+        return Err(FromJsonError::new(format!("expected array of length {}, found array of length {}", len, arr.len())));
+    }
+    Ok(arr)
+}
+
+// 缺少的字段当作 null 处理，这样 Option 类型的字段可以省略
+#[doc(hidden)]
+pub fn from_field<T: FromJson>(object: &HashMap<String, Box<Json>>, key: &str) -> Result<T, FromJsonError> {
+    match object.get(key) {
+        Some(v) => T::from_json(v).map_err(|e| e.with_key(key)),
+        None => T::from_json(&Json::NULL).map_err(|_| FromJsonError::new("missing field").with_key(key)),
+    }
+}
+
+#[doc(hidden)]
+pub fn from_element<T: FromJson>(arr: &[Box<Json>], i: usize) -> Result<T, FromJsonError> {
+    T::from_json(&arr[i]).map_err(|e| e.with_index(i))
+}
+
+// 带数据的枚举成员写成只有一个 key 的对象：{"Variant": value}
+#[doc(hidden)]
+pub fn tagged(tag: &str, value: Json) -> Json {
+    let mut h = HashMap::new();
+    h.insert(tag.to_string(), Box::new(value));
+    Json::OBJECT(h)
+}
+
+macro_rules! impl_tuple {
+    ($len:expr => $($t:ident $i:tt),+) => {
+        impl<$($t: ToJson),+> ToJson for ($($t,)+) {
+            fn to_json(&self) -> Json {
+                Json::ARRAY(vec![$(Box::new(self.$i.to_json())),+])
+            }
+        }
+
+        impl<$($t: FromJson),+> FromJson for ($($t,)+) {
+            fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+                let arr = expect_array(json, $len)?;
+                Ok(($(from_element::<$t>(arr, $i)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(1 => A 0);
+impl_tuple!(2 => A 0, B 1);
+impl_tuple!(3 => A 0, B 1, C 2);
+impl_tuple!(4 => A 0, B 1, C 2, D 3);
+impl_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+impl<T: ToJson> ToJson for HashMap<String, T> {
+    fn to_json(&self) -> Json {
+        Json::OBJECT(self.iter().map(|(k, v)| (k.clone(), Box::new(v.to_json()))).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let h = json.as_object().ok_or_else(|| FromJsonError::expected("object", json))?;
+        h.iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_json(v).map_err(|e| e.with_key(k))?)))
+            .collect()
+    }
+}
+
+impl<T: ToJson> ToJson for BTreeMap<String, T> {
+    fn to_json(&self) -> Json {
+        Json::OBJECT(self.iter().map(|(k, v)| (k.clone(), Box::new(v.to_json()))).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let h = json.as_object().ok_or_else(|| FromJsonError::expected("object", json))?;
+        h.iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_json(v).map_err(|e| e.with_key(k))?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // 同时引入 trait 和同名的 derive 宏
+    use crate::{FromJson, ToJson};
+
+    #[test]
+    fn test_round_trip() {
+        let mut h = HashMap::new();
+        h.insert("k".to_string(), (1u8, 'c', Some(2.5f64)));
+        let v = vec![h];
+        let json = v.to_json();
+        assert_eq!(json, crate::parse(r#"[{"k": [1, "c", 2.5]}]"#).unwrap());
+        assert_eq!(Vec::<HashMap<String, (u8, char, Option<f64>)>>::from_json(&json).unwrap(), v);
+
+        let mut b = BTreeMap::new();
+        b.insert("x".to_string(), Rc::new([true, false]));
+        assert_eq!(BTreeMap::<String, Rc<[bool; 2]>>::from_json(&b.to_json()).unwrap(), b);
+        assert_eq!(Box::new("s").to_json(), Json::STRING("s".to_string()));
+        assert_eq!(Option::<i32>::from_json(&Json::NULL).unwrap(), None);
+        assert_eq!(u128::from_json(&(1u128 << 100).to_json()).unwrap(), 1 << 100);
+        assert_eq!(i128::from_json(&(-5i128).to_json()).unwrap(), -5);
+    }
+
+    #[test]
+    fn test_error_path() {
+        let json = crate::parse(r#"{"items": [{"price": 1}, {"price": 2}, {"price": "3"}]}"#).unwrap();
+        let err = HashMap::<String, Vec<HashMap<String, f64>>>::from_json(&json).unwrap_err();
+        assert_eq!(err.to_string(), "$.items[2].price: expected number, found string");
+        assert_eq!(err.path(), "$.items[2].price");
+        assert_eq!(err.message(), "expected number, found string");
+
+        let err = |json: &str| <(u8, [char; 1])>::from_json(&crate::parse(json).unwrap()).unwrap_err().to_string();
+        assert_eq!(err("[300, [\"a\"]]"), "$[0]: expected u8, found number 300");
+        assert_eq!(err("[1, [\"ab\"]]"), "$[1][0]: expected single character, found string of length 2");
+        assert_eq!(err("[1, []]"), "$[1]: expected array of length 1, found array of length 0");
+        assert_eq!(err("[1]"), "$: expected array of length 2, found array of length 1");
+        assert_eq!(err("{}"), "$: expected array, found object");
+    }
+
+    #[derive(Debug, PartialEq, ToJson, FromJson)]
+    struct Server {
+        /// 文档注释和属性会被跳过
+        pub host: String,
+        pub(crate) port: u16,
+        r#type: Option<Kind>,
+        tags: HashMap<String, Vec<u8>>,
+    }
+
+    #[derive(Debug, PartialEq, ToJson, FromJson)]
+    enum Kind {
+        Primary,
+        Replica(u8),
+        Pair(String, bool),
+        Remote { url: String, fallback: Option<Box<Kind>> },
+    }
+
+    // 字段名和生成代码里的局部变量同名
+    #[derive(Debug, PartialEq, ToJson, FromJson)]
+    enum Shadow {
+        Named { object: Vec<u8>, json: bool, arr: u8 },
+    }
+
+    #[derive(Debug, PartialEq, ToJson, FromJson)]
+    struct Id(u32);
+
+    #[derive(Debug, PartialEq, ToJson, FromJson)]
+    struct Point(f64, f64);
+
+    #[derive(Debug, PartialEq, ToJson, FromJson)]
+    struct Marker;
+
+    #[test]
+    fn test_derive() {
+        let mut tags = HashMap::new();
+        tags.insert("a".to_string(), vec![1, 2]);
+        let server = Server {
+            host: "h".to_string(),
+            port: 80,
+            r#type: Some(Kind::Remote {
+                url: "u".to_string(),
+                fallback: Some(Box::new(Kind::Pair("p".to_string(), true))),
+            }),
+            tags,
+        };
+        let json = server.to_json();
+        let expect = r#"{"host": "h", "port": 80, "tags": {"a": [1, 2]},
+            "type": {"Remote": {"url": "u", "fallback": {"Pair": ["p", true]}}}}"#;
+        assert_eq!(json, crate::parse(expect).unwrap());
+        assert_eq!(Server::from_json(&json).unwrap(), server);
+
+        assert_eq!(Kind::Primary.to_json(), Json::STRING("Primary".to_string()));
+        assert_eq!(Kind::from_json(&json!({"Replica": 3})).unwrap(), Kind::Replica(3));
+        assert_eq!(Id(5).to_json(), Json::NUMBER(5.0));
+        assert_eq!(Point::from_json(&json!([1, 2.5])).unwrap(), Point(1.0, 2.5));
+        assert_eq!(Marker::from_json(&Marker.to_json()).unwrap(), Marker);
+
+        let shadow = Shadow::Named { object: vec![1], json: true, arr: 2 };
+        let json = shadow.to_json();
+        assert_eq!(json, json!({"Named": {"object": [1], "json": true, "arr": 2}}));
+        assert_eq!(Shadow::from_json(&json).unwrap(), shadow);
+    }
+
+    #[test]
+    fn test_derive_errors() {
+        let err = |json: Json| Server::from_json(&json).unwrap_err().to_string();
+        assert_eq!(err(json!({"host": "h", "tags": {}})), "$.port: missing field");
+        assert_eq!(err(json!({"host": "h", "port": 1, "tags": {"x": [1, "2"]}})), "$.tags.x[1]: expected number, found string");
+        assert_eq!(err(json!({"host": "h", "port": 1, "tags": {}, "type": "Backup"})), "$.type: unknown variant `Backup`");
+        assert_eq!(
+            err(json!({"host": "h", "port": 1, "tags": {}, "type": {"Remote": {"url": 1}}})),
+            "$.type.Remote.url: expected string, found number"
+        );
+        assert_eq!(err(json!([])), "$: expected object, found array");
+        assert_eq!(Point::from_json(&json!([1])).unwrap_err().to_string(), "$: expected array of length 2, found array of length 1");
+    }
+}
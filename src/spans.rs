@@ -0,0 +1,97 @@
+// 记录每个值和每个 key 在输入中的位置，按 JSON path 查找
+// 解析时只记下字节范围，行号和列号在解析结束后统一计算
+// 列号按字符计算，一个多字节的 UTF-8 字符只算一列
+
+use std::collections::HashMap;
+
+use crate::{path_index, path_key};
+
+/// Where a value or key appears in the input. `line` and `column` are 1-based and refer to `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Spans of every value and object key of a parsed text, by path such as `$.servers[3].port`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SpanMap {
+    values: HashMap<String, Span>,
+    keys: HashMap<String, Span>,
+}
+
+impl SpanMap {
+    // 输入开头跳过了 n 个字节（BOM）时，把位置换回原来输入里的偏移
+    pub(crate) fn shift(&mut self, n: usize) {
+        for span in self.values.values_mut().chain(self.keys.values_mut()) {
+            span.start += n;
+            span.end += n;
+        }
+    }
+
+    /// The span of the value at `path`.
+    pub fn value(&self, path: &str) -> Option<Span> {
+        self.values.get(path).copied()
+    }
+
+    /// The span of the quoted key of the member at `path`.
+    pub fn key(&self, path: &str) -> Option<Span> {
+        self.keys.get(path).copied()
+    }
+}
+
+pub(crate) struct SpanRecorder {
+    path: String,
+    values: Vec<(String, usize, usize)>,
+    keys: Vec<(String, usize, usize)>,
+}
+
+impl SpanRecorder {
+    pub(crate) fn new() -> Self {
+        SpanRecorder {
+            path: String::from("$"),
+            values: vec![],
+            keys: vec![],
+        }
+    }
+
+    pub(crate) fn value(&mut self, start: usize, end: usize) {
+        self.values.push((self.path.clone(), start, end));
+    }
+
+    // 进入对象成员，返回离开时要恢复的路径长度
+    pub(crate) fn enter_key(&mut self, k: &str, start: usize, end: usize) -> usize {
+        let len = self.path.len();
+        self.path = path_key(&self.path, k);
+        self.keys.push((self.path.clone(), start, end));
+        len
+    }
+
+    pub(crate) fn enter_index(&mut self, i: usize) -> usize {
+        let len = self.path.len();
+        self.path = path_index(&self.path, i);
+        len
+    }
+
+    pub(crate) fn leave(&mut self, len: usize) {
+        self.path.truncate(len);
+    }
+
+    pub(crate) fn finish(self, bytes: &[u8]) -> SpanMap {
+        let mut line_starts = vec![0];
+        line_starts.extend(bytes.iter().enumerate().filter(|(_, b)| **b == b'\n').map(|(i, _)| i + 1));
+        let span = |start: usize, end: usize| {
+            let line = line_starts.partition_point(|s| *s <= start);
+            let prefix = &bytes[line_starts[line - 1]..start];
+            // UTF-8 的后续字节都是 10xxxxxx
+            let column = prefix.iter().filter(|b| **b & 0xC0 != 0x80).count() + 1;
+            Span { start, end, line, column }
+        };
+        SpanMap {
+            values: self.values.into_iter().map(|(p, s, e)| (p, span(s, e))).collect(),
+            keys: self.keys.into_iter().map(|(p, s, e)| (p, span(s, e))).collect(),
+        }
+    }
+}